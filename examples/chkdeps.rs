@@ -7,9 +7,11 @@ use std::{collections::HashSet, error::Error};
 mod common;
 use common::Scope;
 
+#[cfg(windows)]
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
     let Some(dependents) = wixpkgdep::check_dependents(
+        &wixpkgdep::WindowsRegistry,
         &args.provider_key,
         args.scope.into(),
         Default::default(),
@@ -30,6 +32,12 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[cfg(not(windows))]
+fn main() -> Result<(), Box<dyn Error>> {
+    let _ = Args::parse();
+    Err(wixpkgdep::Error::NotSupported.into())
+}
+
 /// Checks for dependents of a provider key.
 ///
 /// If any dependents are found they are printed and the process terminates with exit code 1.
@@ -50,6 +58,7 @@ struct Args {
 }
 
 impl Args {
+    #[cfg_attr(not(windows), allow(dead_code))]
     fn ignored(&self) -> Option<HashSet<String>> {
         self.ignore
             .as_ref()
@@ -3,18 +3,13 @@
 
 use clap::{builder::PossibleValue, ValueEnum};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum Scope {
+    #[default]
     Machine,
     User,
 }
 
-impl Default for Scope {
-    fn default() -> Self {
-        Self::Machine
-    }
-}
-
 impl std::fmt::Display for Scope {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
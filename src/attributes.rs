@@ -0,0 +1,320 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use crate::Error;
+use std::{
+    fmt::Display,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
+    str::FromStr,
+};
+
+/// Attributes of a provider or dependent stored in its `Attributes` value.
+///
+/// Bits not defined as constants are preserved so that attributes read from a store can be written back unchanged.
+/// Attributes format as and parse from `|`-separated names as used in WiX authoring e.g., `MinVersionInclusive|MaxVersionInclusive`,
+/// with any undefined bits formatted as a hexadecimal number.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Attributes(u32);
+
+impl Attributes {
+    /// No attributes.
+    pub const NONE: Attributes = Attributes(0);
+
+    /// The minimum version of a dependency is inclusive.
+    pub const MIN_VERSION_INCLUSIVE: Attributes = Attributes(0x100);
+
+    /// The maximum version of a dependency is inclusive.
+    pub const MAX_VERSION_INCLUSIVE: Attributes = Attributes(0x200);
+
+    /// The provider was registered by a bundle.
+    pub const BUNDLE: Attributes = Attributes(0x10000);
+
+    /// The provider was imported from a package rather than authored.
+    pub const IMPORTED: Attributes = Attributes(0x20000);
+
+    const NAMES: [(&'static str, Attributes); 4] = [
+        ("MinVersionInclusive", Attributes::MIN_VERSION_INCLUSIVE),
+        ("MaxVersionInclusive", Attributes::MAX_VERSION_INCLUSIVE),
+        ("Bundle", Attributes::BUNDLE),
+        ("Imported", Attributes::IMPORTED),
+    ];
+
+    /// Creates attributes from raw bits, including any bits not defined as constants.
+    pub const fn from_bits(bits: u32) -> Self {
+        Attributes(bits)
+    }
+
+    /// Gets the raw bits.
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Gets whether no bits are set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Gets whether all bits in `other` are set.
+    pub const fn contains(&self, other: Attributes) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Gets whether any bits in `other` are set.
+    pub const fn intersects(&self, other: Attributes) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Gets the bits set in either `self` or `other`.
+    pub const fn union(self, other: Attributes) -> Self {
+        Attributes(self.0 | other.0)
+    }
+
+    /// Gets the bits set in both `self` and `other`.
+    pub const fn intersection(self, other: Attributes) -> Self {
+        Attributes(self.0 & other.0)
+    }
+
+    /// Gets the bits set in `self` but not in `other`.
+    pub const fn difference(self, other: Attributes) -> Self {
+        Attributes(self.0 & !other.0)
+    }
+}
+
+impl BitAnd for Attributes {
+    type Output = Self;
+    // cspell:ignore bitand
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.intersection(rhs)
+    }
+}
+
+impl BitAndAssign for Attributes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        *self = self.intersection(rhs);
+    }
+}
+
+impl BitOr for Attributes {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for Attributes {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+impl Display for Attributes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "None");
+        }
+
+        let mut remaining = *self;
+        let mut names = Vec::new();
+        for (name, attribute) in Attributes::NAMES {
+            if remaining.contains(attribute) {
+                names.push(name.to_string());
+                remaining = remaining.difference(attribute);
+            }
+        }
+        if !remaining.is_empty() {
+            names.push(format!("{:#x}", remaining.0));
+        }
+
+        write!(f, "{}", names.join("|"))
+    }
+}
+
+impl FromStr for Attributes {
+    type Err = Error;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut attributes = Attributes::NONE;
+        for part in s.split('|').map(str::trim) {
+            if part.is_empty() || part.eq_ignore_ascii_case("None") {
+                continue;
+            }
+
+            if let Some((_, attribute)) = Attributes::NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+            {
+                attributes |= *attribute;
+                continue;
+            }
+
+            let bits = match part.strip_prefix("0x").or_else(|| part.strip_prefix("0X")) {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => part.parse::<u32>(),
+            }
+            .map_err(|_| Error::Format)?;
+            attributes |= Attributes(bits);
+        }
+
+        Ok(attributes)
+    }
+}
+
+impl From<u32> for Attributes {
+    fn from(value: u32) -> Self {
+        Attributes(value)
+    }
+}
+
+impl From<Attributes> for u32 {
+    fn from(value: Attributes) -> Self {
+        value.0
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Attributes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Attributes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl serde::de::Visitor<'_> for Visitor {
+            type Value = Attributes;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "attribute names separated by '|' or an integer")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse()
+                    .map_err(|_| E::custom(format!("invalid attributes {:?}", v)))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                u32::try_from(v)
+                    .map(Attributes)
+                    .map_err(|_| E::custom(format!("invalid attributes {}", v)))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_bitor() {
+        let attributes = Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE;
+        assert_eq!(attributes.bits(), 0x300);
+        assert!(attributes.contains(Attributes::MIN_VERSION_INCLUSIVE));
+        assert!(attributes.contains(Attributes::MAX_VERSION_INCLUSIVE));
+    }
+
+    #[test]
+    fn attributes_bitand() {
+        let attributes = Attributes::from_bits(0x300) & Attributes::MAX_VERSION_INCLUSIVE;
+        assert_eq!(attributes, Attributes::MAX_VERSION_INCLUSIVE);
+        assert!(!attributes.contains(Attributes::MIN_VERSION_INCLUSIVE));
+    }
+
+    #[test]
+    fn attributes_unknown_bits() {
+        let attributes = Attributes::from(0x10300u32);
+        assert!(attributes.contains(Attributes::MIN_VERSION_INCLUSIVE));
+        assert_eq!(u32::from(attributes), 0x10300);
+    }
+
+    #[test]
+    fn attributes_empty() {
+        assert!(Attributes::default().is_empty());
+        assert!(Attributes::NONE.contains(Attributes::NONE));
+        assert!(!Attributes::MIN_VERSION_INCLUSIVE.is_empty());
+    }
+
+    #[test]
+    fn attributes_set_operations() {
+        let both = Attributes::MIN_VERSION_INCLUSIVE.union(Attributes::MAX_VERSION_INCLUSIVE);
+        assert_eq!(
+            both.intersection(Attributes::MAX_VERSION_INCLUSIVE),
+            Attributes::MAX_VERSION_INCLUSIVE
+        );
+        assert_eq!(
+            both.difference(Attributes::MAX_VERSION_INCLUSIVE),
+            Attributes::MIN_VERSION_INCLUSIVE
+        );
+        assert!(both.intersects(Attributes::MIN_VERSION_INCLUSIVE | Attributes::BUNDLE));
+        assert!(!both.intersects(Attributes::BUNDLE));
+
+        let mut attributes = Attributes::NONE;
+        attributes |= Attributes::BUNDLE;
+        attributes |= Attributes::IMPORTED;
+        attributes &= Attributes::IMPORTED;
+        assert_eq!(attributes, Attributes::IMPORTED);
+    }
+
+    #[test]
+    fn attributes_to_string() {
+        assert_eq!(Attributes::NONE.to_string(), "None");
+        assert_eq!(
+            (Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE).to_string(),
+            "MinVersionInclusive|MaxVersionInclusive"
+        );
+        assert_eq!(Attributes::from_bits(0x10401).to_string(), "Bundle|0x401");
+    }
+
+    #[test]
+    fn attributes_from_str() {
+        assert_eq!("None".parse::<Attributes>().unwrap(), Attributes::NONE);
+        assert_eq!("".parse::<Attributes>().unwrap(), Attributes::NONE);
+        assert_eq!(
+            "maxversioninclusive | MinVersionInclusive"
+                .parse::<Attributes>()
+                .unwrap(),
+            Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE
+        );
+        assert_eq!(
+            "Bundle|0x401".parse::<Attributes>().unwrap(),
+            Attributes::from_bits(0x10401)
+        );
+        assert_eq!(
+            "256".parse::<Attributes>().unwrap(),
+            Attributes::MIN_VERSION_INCLUSIVE
+        );
+        assert_eq!("Vital".parse::<Attributes>().unwrap_err(), Error::Format);
+    }
+
+    #[test]
+    fn attributes_round_trip() {
+        let attributes = Attributes::from_bits(0xf0030300);
+        assert_eq!(
+            attributes.to_string().parse::<Attributes>().unwrap(),
+            attributes
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn attributes_serde() {
+        let attributes = Attributes::MIN_VERSION_INCLUSIVE | Attributes::from_bits(0x1);
+        assert_eq!(
+            serde_json::to_string(&attributes).unwrap(),
+            r#""MinVersionInclusive|0x1""#
+        );
+        assert_eq!(
+            serde_json::from_str::<Attributes>(r#""MinVersionInclusive|0x1""#).unwrap(),
+            attributes
+        );
+        assert_eq!(
+            serde_json::from_str::<Attributes>("257").unwrap(),
+            attributes
+        );
+        assert!(serde_json::from_str::<Attributes>(r#""Vital""#).is_err());
+    }
+}
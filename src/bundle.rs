@@ -0,0 +1,442 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{fmt::Display, path::Path};
+
+use roxmltree::{Document, Node};
+
+use crate::{
+    wxs::{error, parse, version, yes},
+    Dependency, Provider, Result, Scope, Snapshot, SnapshotEntry, Version,
+};
+
+// cspell:ignore roxmltree
+
+/// How a bundle is related to another bundle.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RelatedBundleAction {
+    /// The related bundle is only detected.
+    Detect,
+
+    /// The bundle upgrades the related bundle.
+    Upgrade,
+
+    /// The bundle is an addon to the related bundle.
+    Addon,
+
+    /// The bundle patches the related bundle.
+    Patch,
+}
+
+impl Display for RelatedBundleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            RelatedBundleAction::Detect => "Detect",
+            RelatedBundleAction::Upgrade => "Upgrade",
+            RelatedBundleAction::Addon => "Addon",
+            RelatedBundleAction::Patch => "Patch",
+        };
+        f.write_str(action)
+    }
+}
+
+/// A bundle related by upgrade code from a `RelatedBundle` element.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelatedBundle {
+    /// The upgrade code of the related bundle.
+    pub id: String,
+
+    /// How the bundle is related.
+    pub action: RelatedBundleAction,
+}
+
+/// A package in the bundle chain.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BundlePackage {
+    /// The package identifier within the bundle.
+    pub id: String,
+
+    /// The scope into which the package and its providers are registered, which defaults to the bundle's scope.
+    pub scope: Scope,
+
+    /// Providers declared by the package, with versions and display names defaulted from the package.
+    pub providers: Vec<Provider>,
+}
+
+/// Dependency data from a Burn bundle manifest.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BundleManifest {
+    /// The provider registered for the bundle itself, if the bundle is registered.
+    pub bundle: Option<Provider>,
+
+    /// The scope into which the bundle is registered.
+    pub scope: Scope,
+
+    /// Related bundles in document order.
+    pub related_bundles: Vec<RelatedBundle>,
+
+    /// Packages in chain order.
+    pub packages: Vec<BundlePackage>,
+}
+
+impl BundleManifest {
+    /// Parses a Burn bundle manifest.
+    ///
+    /// The manifest must already be extracted from the bundle's UX container. Elements are matched by name in any
+    /// namespace, so manifests from WiX v3 and v4 are supported. Returns [`Error::Parse`](crate::Error::Parse)
+    /// with the line and column of invalid XML or data.
+    pub fn from_xml(s: &str) -> Result<Self> {
+        let doc = parse(s)?;
+
+        let root = doc.root_element();
+        let child = |name| {
+            root.children()
+                .filter(Node::is_element)
+                .filter(move |n| n.tag_name().name() == name)
+        };
+
+        let mut related_bundles = Vec::new();
+        for node in child("RelatedBundle") {
+            let id = required(&doc, node, "Id")?;
+            let action = match node.attribute("Action") {
+                Some(action) => match action.to_lowercase().as_str() {
+                    "detect" => RelatedBundleAction::Detect,
+                    "upgrade" => RelatedBundleAction::Upgrade,
+                    "addon" => RelatedBundleAction::Addon,
+                    "patch" => RelatedBundleAction::Patch,
+                    _ => {
+                        return Err(error(
+                            &doc,
+                            node,
+                            format!("invalid Action value {:?}", action),
+                        ))
+                    }
+                },
+                None => return Err(error(&doc, node, "missing Action attribute")),
+            };
+            related_bundles.push(RelatedBundle {
+                id: id.to_string(),
+                action,
+            });
+        }
+
+        let mut scope = Scope::User;
+        let mut bundle = None;
+        if let Some(node) = child("Registration").next() {
+            if yes(&doc, node, "PerMachine")? {
+                scope = Scope::Machine;
+            }
+
+            // Burn uses the bundle code as the provider key unless one was authored.
+            let id = required(&doc, node, "Id")?;
+            let arp = node
+                .children()
+                .find(|n| n.is_element() && n.tag_name().name() == "Arp");
+            bundle = Some(Provider {
+                key: node.attribute("ProviderKey").unwrap_or(id).to_string(),
+                name: arp
+                    .and_then(|n| n.attribute("DisplayName"))
+                    .unwrap_or_default()
+                    .to_string(),
+                version: optional_version(&doc, node, node.attribute("Version"))?
+                    .unwrap_or_default(),
+                id: Some(id.to_string()),
+                attributes: None,
+            });
+        }
+
+        let mut packages = Vec::new();
+        for chain in child("Chain") {
+            for node in chain.children().filter(Node::is_element) {
+                if !node.tag_name().name().ends_with("Package") {
+                    continue;
+                }
+
+                let id = required(&doc, node, "Id")?;
+                let scope = match node.attribute("PerMachine") {
+                    Some(_) if yes(&doc, node, "PerMachine")? => Scope::Machine,
+                    Some(_) => Scope::User,
+                    None => scope,
+                };
+                let package_version = optional_version(&doc, node, node.attribute("Version"))?;
+                let mut providers = Vec::new();
+                for provides in node
+                    .children()
+                    .filter(|n| n.is_element() && n.tag_name().name() == "Provides")
+                {
+                    providers.push(Provider {
+                        key: required(&doc, provides, "Key")?.to_string(),
+                        name: provides
+                            .attribute("DisplayName")
+                            .or_else(|| node.attribute("DisplayName"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        version: optional_version(&doc, provides, provides.attribute("Version"))?
+                            .or(package_version)
+                            .unwrap_or_default(),
+                        id: node.attribute("ProductCode").map(str::to_string),
+                        attributes: None,
+                    });
+                }
+
+                packages.push(BundlePackage {
+                    id: id.to_string(),
+                    scope,
+                    providers,
+                });
+            }
+        }
+
+        Ok(BundleManifest {
+            bundle,
+            scope,
+            related_bundles,
+            packages,
+        })
+    }
+
+    /// Reads a Burn bundle manifest from a file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        BundleManifest::from_xml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Gets the dependents Burn registers for the bundle as the scope and key of each package provider and the bundle
+    /// dependency.
+    ///
+    /// Burn registers the bundle as a dependent of every package provider without a version range, in the scope of the
+    /// package. An unregistered bundle has no dependents.
+    pub fn dependents(&self) -> Vec<(Scope, String, Dependency)> {
+        let Some(bundle) = &self.bundle else {
+            return Vec::new();
+        };
+
+        self.packages
+            .iter()
+            .flat_map(|package| package.providers.iter().map(|p| (package.scope, p)))
+            .map(|(scope, p)| {
+                (
+                    scope,
+                    p.key.clone(),
+                    Dependency {
+                        key: bundle.key.clone(),
+                        min_version: None,
+                        max_version: None,
+                        attributes: None,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Gets the providers and dependents Burn registers when the bundle is installed.
+    ///
+    /// Compare with a [`Snapshot`] captured after installation using [`Snapshot::diff`].
+    pub fn snapshot(&self) -> Snapshot {
+        let mut entries: Vec<SnapshotEntry> = Vec::new();
+        let providers = self.bundle.iter().map(|p| (self.scope, p)).chain(
+            self.packages
+                .iter()
+                .flat_map(|package| package.providers.iter().map(|p| (package.scope, p))),
+        );
+        for (scope, provider) in providers {
+            if !entries
+                .iter()
+                .any(|e| e.scope == scope && e.key.eq_ignore_ascii_case(&provider.key))
+            {
+                entries.push(SnapshotEntry {
+                    scope,
+                    key: provider.key.clone(),
+                    provider: Some(provider.clone()),
+                    dependents: Vec::new(),
+                });
+            }
+        }
+
+        for (scope, key, dependent) in self.dependents() {
+            if let Some(entry) = entries
+                .iter_mut()
+                .find(|e| e.scope == scope && e.key.eq_ignore_ascii_case(&key))
+            {
+                if !entry
+                    .dependents
+                    .iter()
+                    .any(|d| d.key.eq_ignore_ascii_case(&dependent.key))
+                {
+                    entry.dependents.push(dependent);
+                }
+            }
+        }
+
+        entries.into_iter().collect()
+    }
+}
+
+fn required<'a>(doc: &Document, node: Node<'a, '_>, name: &str) -> Result<&'a str> {
+    node.attribute(name)
+        .ok_or_else(|| error(doc, node, format!("missing {} attribute", name)))
+}
+
+fn optional_version(doc: &Document, node: Node, value: Option<&str>) -> Result<Option<Version>> {
+    value.map(|v| version(doc, node, v)).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    const MANIFEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<BurnManifest xmlns="http://wixtoolset.org/schemas/v4/2008/Burn">
+  <RelatedBundle Id="{A0000000-0000-0000-0000-000000000000}" Action="Upgrade" />
+  <RelatedBundle Id="{B0000000-0000-0000-0000-000000000000}" Action="Detect" />
+  <Registration Id="{C0000000-0000-0000-0000-000000000000}" ExecutableName="setup.exe" PerMachine="yes" Version="1.2.0.0" ProviderKey="Example.Bundle">
+    <Arp Register="yes" DisplayName="Example Suite" DisplayVersion="1.2.0.0" />
+  </Registration>
+  <Chain>
+    <MsiPackage Id="Runtime.msi" ProductCode="{D0000000-0000-0000-0000-000000000000}" Version="2.1.0.0">
+      <Provides Key="Example.Runtime" DisplayName="Example Runtime" />
+    </MsiPackage>
+    <ExePackage Id="App.exe" Version="1.2.0.0">
+      <Provides Key="Example.App" Version="1.2.3" />
+      <Provides Key="example.runtime" />
+    </ExePackage>
+    <MsiPackage Id="Tools.msi" PerMachine="no" Version="3.0.0.0">
+      <Provides Key="Example.Tools" />
+    </MsiPackage>
+    <RollbackBoundary Id="Boundary" />
+  </Chain>
+</BurnManifest>"#;
+
+    #[test]
+    fn from_xml() {
+        let manifest = BundleManifest::from_xml(MANIFEST).unwrap();
+        assert_eq!(manifest.scope, Scope::Machine);
+        assert_eq!(
+            manifest.related_bundles[0],
+            RelatedBundle {
+                id: "{A0000000-0000-0000-0000-000000000000}".to_string(),
+                action: RelatedBundleAction::Upgrade,
+            }
+        );
+        assert_eq!(
+            manifest.related_bundles[1].action,
+            RelatedBundleAction::Detect
+        );
+
+        let bundle = manifest.bundle.as_ref().unwrap();
+        assert_eq!(bundle.key, "Example.Bundle");
+        assert_eq!(bundle.name, "Example Suite");
+        assert_eq!(bundle.version, Version::from([1, 2, 0, 0]));
+        assert_eq!(
+            bundle.id.as_deref(),
+            Some("{C0000000-0000-0000-0000-000000000000}")
+        );
+
+        assert_eq!(manifest.packages.len(), 3);
+        assert_eq!(manifest.packages[0].scope, Scope::Machine);
+        assert_eq!(manifest.packages[2].scope, Scope::User);
+        let runtime = &manifest.packages[0].providers[0];
+        assert_eq!(runtime.key, "Example.Runtime");
+        assert_eq!(runtime.name, "Example Runtime");
+        assert_eq!(runtime.version, Version::from([2, 1, 0, 0]));
+        assert_eq!(
+            runtime.id.as_deref(),
+            Some("{D0000000-0000-0000-0000-000000000000}")
+        );
+
+        let app = &manifest.packages[1];
+        assert_eq!(app.id, "App.exe");
+        assert_eq!(app.providers[0].version, Version::from([1, 2, 3, 0]));
+        assert_eq!(app.providers[0].id, None);
+    }
+
+    #[test]
+    fn from_xml_default_provider_key() {
+        let manifest = BundleManifest::from_xml(
+            r#"<BurnManifest><Registration Id="{C0000000-0000-0000-0000-000000000000}" /></BurnManifest>"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.scope, Scope::User);
+        assert_eq!(
+            manifest.bundle.unwrap().key,
+            "{C0000000-0000-0000-0000-000000000000}"
+        );
+    }
+
+    #[test]
+    fn from_xml_errors() {
+        let err = |s| BundleManifest::from_xml(s).unwrap_err();
+
+        assert_eq!(
+            err("<BurnManifest>\n  <RelatedBundle Id=\"a\" Action=\"Remove\" />\n</BurnManifest>"),
+            Error::Parse {
+                line: 2,
+                column: 3,
+                message: "invalid Action value \"Remove\"".to_string(),
+            }
+        );
+        assert!(matches!(
+            err("<BurnManifest><Chain><MsiPackage Id=\"a\"><Provides /></MsiPackage></Chain></BurnManifest>"),
+            Error::Parse { message, .. } if message == "missing Key attribute"
+        ));
+        assert!(matches!(
+            err("<BurnManifest><Chain><MsiPackage Id=\"a\" Version=\"1.x\" /></Chain></BurnManifest>"),
+            Error::Parse { message, .. } if message == "invalid version \"1.x\""
+        ));
+    }
+
+    #[test]
+    fn dependents() {
+        let manifest = BundleManifest::from_xml(MANIFEST).unwrap();
+        let dependents = manifest.dependents();
+        assert_eq!(dependents.len(), 4);
+        assert_eq!(dependents[0].0, Scope::Machine);
+        assert_eq!(dependents[0].1, "Example.Runtime");
+        assert_eq!(dependents[0].2.key, "Example.Bundle");
+        assert_eq!(dependents[0].2.range().to_string(), "(,)");
+        assert_eq!(dependents[3].0, Scope::User);
+        assert_eq!(dependents[3].1, "Example.Tools");
+
+        let unregistered = BundleManifest {
+            bundle: None,
+            ..manifest
+        };
+        assert!(unregistered.dependents().is_empty());
+    }
+
+    #[test]
+    fn snapshot() {
+        let snapshot = BundleManifest::from_xml(MANIFEST).unwrap().snapshot();
+        assert_eq!(snapshot.entries().len(), 4);
+
+        let bundle = snapshot.get(Scope::Machine, "example.bundle").unwrap();
+        assert!(bundle.dependents.is_empty());
+
+        let runtime = snapshot.get(Scope::Machine, "Example.Runtime").unwrap();
+        assert_eq!(runtime.provider.as_ref().unwrap().name, "Example Runtime");
+        assert_eq!(runtime.dependents.len(), 1);
+        assert_eq!(runtime.dependents[0].key, "Example.Bundle");
+
+        let app = snapshot.get(Scope::Machine, "Example.App").unwrap();
+        assert_eq!(app.dependents[0].key, "Example.Bundle");
+
+        let tools = snapshot.get(Scope::User, "Example.Tools").unwrap();
+        assert_eq!(tools.dependents[0].key, "Example.Bundle");
+        assert!(snapshot.get(Scope::Machine, "Example.Tools").is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn manifest_serde() {
+        let manifest = BundleManifest::from_xml(MANIFEST).unwrap();
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(json.contains(r#""action":"Upgrade""#));
+
+        let actual: BundleManifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual, manifest);
+    }
+}
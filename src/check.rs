@@ -0,0 +1,265 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::fmt::Display;
+
+use crate::store::StoreKey;
+use crate::{Error, Result, Scope, Version, VersionRange};
+
+/// The outcome of checking that a dependency is registered and within the requested version range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DependencyCheck {
+    /// Provider key of the dependency.
+    pub key: String,
+
+    /// Display name of the provider, if registered with one.
+    pub name: String,
+
+    /// Scope in which the provider satisfied the dependency or, if not satisfied, was checked.
+    pub scope: Scope,
+
+    /// Requested range of provider versions.
+    pub range: VersionRange,
+
+    /// Version of the provider, if registered with a valid version.
+    pub installed: Option<Version>,
+
+    /// Why the dependency is not satisfied, or `None` if it is satisfied.
+    pub reason: Option<Unsatisfied>,
+}
+
+/// Why a dependency is not satisfied.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unsatisfied {
+    /// The provider is not registered.
+    MissingProvider,
+
+    /// The provider is registered without a `Version` value.
+    MissingVersion,
+
+    /// The provider `Version` value is not a valid version.
+    MalformedVersion,
+
+    /// The provider version is less than the minimum version.
+    BelowMinimum,
+
+    /// The provider version is greater than the maximum version.
+    AboveMaximum,
+}
+
+impl DependencyCheck {
+    /// Gets whether the dependency is satisfied.
+    pub fn is_satisfied(&self) -> bool {
+        self.reason.is_none()
+    }
+
+    pub(crate) fn check(
+        root: Option<&impl StoreKey>,
+        provider_key: &str,
+        scope: Scope,
+        range: VersionRange,
+    ) -> Result<Self> {
+        // Equivalent to deputil:DepCheckDependency.
+        let mut check = DependencyCheck {
+            key: provider_key.to_string(),
+            name: String::new(),
+            scope,
+            range,
+            installed: None,
+            reason: None,
+        };
+
+        let key = match root.map(|key| key.open_subkey(provider_key)) {
+            None | Some(Err(Error::NotFound)) => {
+                check.reason = Some(Unsatisfied::MissingProvider);
+                return Ok(check);
+            }
+            Some(key) => key?,
+        };
+
+        check.name = key
+            .value(Some("DisplayName"))
+            .and_then(|v| v.to_string())
+            .unwrap_or_default();
+
+        let version = match key.value(Some("Version")) {
+            Err(Error::NotFound) => {
+                check.reason = Some(Unsatisfied::MissingVersion);
+                return Ok(check);
+            }
+            value => match value?.to_version() {
+                Ok(version) => version,
+                Err(_) => {
+                    check.reason = Some(Unsatisfied::MalformedVersion);
+                    return Ok(check);
+                }
+            },
+        };
+
+        check.installed = Some(version);
+        if !range.contains(version) {
+            let below = range
+                .min()
+                .is_some_and(|min| version < min || version == min && !range.min_inclusive());
+            check.reason = Some(if below {
+                Unsatisfied::BelowMinimum
+            } else {
+                Unsatisfied::AboveMaximum
+            });
+        }
+
+        Ok(check)
+    }
+}
+
+impl Display for DependencyCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = if self.name.is_empty() {
+            &self.key
+        } else {
+            &self.name
+        };
+
+        match (self.reason, self.installed) {
+            (None, Some(installed)) => write!(f, "{} {} is installed", name, installed),
+            (Some(Unsatisfied::MissingProvider), _) => write!(f, "{} is not installed", name),
+            (Some(Unsatisfied::MissingVersion), _) => {
+                write!(f, "{} is installed without a version", name)
+            }
+            (Some(Unsatisfied::MalformedVersion), _) => {
+                write!(f, "{} is installed with an invalid version", name)
+            }
+            (Some(Unsatisfied::BelowMinimum), Some(installed)) => {
+                write!(f, "{} {} is installed but ", name, installed)?;
+                match self.range.min() {
+                    Some(min) if self.range.min_inclusive() => {
+                        write!(f, "{} or later is required", min)
+                    }
+                    Some(min) => write!(f, "a version later than {} is required", min),
+                    None => write!(f, "{} is required", self.range),
+                }
+            }
+            (Some(Unsatisfied::AboveMaximum), Some(installed)) => {
+                write!(f, "{} {} is installed but ", name, installed)?;
+                match self.range.max() {
+                    Some(max) if self.range.max_inclusive() => {
+                        write!(f, "{} or earlier is required", max)
+                    }
+                    Some(max) => write!(f, "a version earlier than {} is required", max),
+                    None => write!(f, "{} is required", self.range),
+                }
+            }
+            _ => write!(f, "{} is not satisfied", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Attributes, DependencyStore, MemoryStore, Provider};
+
+    fn check(store: &MemoryStore, range: &str) -> DependencyCheck {
+        let root = store.open(Scope::Machine).ok();
+        DependencyCheck::check(
+            root.as_ref(),
+            "runtime",
+            Scope::Machine,
+            range.parse().unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn store(version: &str) -> MemoryStore {
+        let store = MemoryStore::new();
+        Provider {
+            key: "runtime".to_string(),
+            name: "Runtime".to_string(),
+            version: Version::try_from(version).unwrap(),
+            ..Default::default()
+        }
+        .register(&store, Scope::Machine)
+        .unwrap();
+        store
+    }
+
+    #[test]
+    fn check_satisfied() {
+        let check = check(&store("2.1"), "[2.0,3.0)");
+        assert!(check.is_satisfied());
+        assert_eq!(check.installed, Some(Version::from([2, 1, 0, 0])));
+        assert_eq!(check.to_string(), "Runtime 2.1.0.0 is installed");
+    }
+
+    #[test]
+    fn check_missing_provider() {
+        let check = check(&MemoryStore::new(), "(,)");
+        assert_eq!(check.reason, Some(Unsatisfied::MissingProvider));
+        assert_eq!(check.to_string(), "runtime is not installed");
+
+        let store = store("1.0");
+        store
+            .create(Scope::Machine)
+            .unwrap()
+            .delete_subkey("runtime")
+            .unwrap();
+        let check = self::check(&store, "(,)");
+        assert_eq!(check.reason, Some(Unsatisfied::MissingProvider));
+    }
+
+    #[test]
+    fn check_missing_version() {
+        let store = store("1.0");
+        store
+            .open(Scope::Machine)
+            .and_then(|k| k.open_subkey("runtime"))
+            .and_then(|k| k.delete_value(Some("Version")))
+            .unwrap();
+
+        let check = check(&store, "(,)");
+        assert_eq!(check.reason, Some(Unsatisfied::MissingVersion));
+        assert_eq!(check.to_string(), "Runtime is installed without a version");
+    }
+
+    #[test]
+    fn check_malformed_version() {
+        let store = store("1.0");
+        store
+            .open(Scope::Machine)
+            .and_then(|k| k.open_subkey("runtime"))
+            .and_then(|k| k.set_value(Some("Version"), crate::Data::DWord(1)))
+            .unwrap();
+
+        let check = check(&store, "(,)");
+        assert_eq!(check.reason, Some(Unsatisfied::MalformedVersion));
+        assert_eq!(check.installed, None);
+    }
+
+    #[test]
+    fn check_below_minimum() {
+        let check = check(&store("2.1"), "[3.0,)");
+        assert_eq!(check.reason, Some(Unsatisfied::BelowMinimum));
+        assert_eq!(
+            check.to_string(),
+            "Runtime 2.1.0.0 is installed but 3.0.0.0 or later is required"
+        );
+
+        let check = self::check(&store("3.0"), "(3.0,4.0]");
+        assert_eq!(check.reason, Some(Unsatisfied::BelowMinimum));
+        assert_eq!(
+            check.to_string(),
+            "Runtime 3.0.0.0 is installed but a version later than 3.0.0.0 is required"
+        );
+    }
+
+    #[test]
+    fn check_above_maximum() {
+        let check = check(&store("3.0"), "[2.0,3.0)");
+        assert_eq!(check.reason, Some(Unsatisfied::AboveMaximum));
+        assert_eq!(check.range.attributes(), Attributes::MIN_VERSION_INCLUSIVE);
+        assert_eq!(
+            check.to_string(),
+            "Runtime 3.0.0.0 is installed but a version earlier than 3.0.0.0 is required"
+        );
+    }
+}
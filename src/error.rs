@@ -1,13 +1,27 @@
 // Copyright 2023 Heath Stewart.
 // Licensed under the MIT License. See LICENSE.txt in the project root for license information.
 
+use crate::{Dependency, DependencyCheck};
 use std::fmt::Display;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Error {
     Format,
+    HasDependents(Vec<Dependency>),
+    InvalidProvider(String, Box<Error>),
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
     NotFound,
     NotSupported,
+    Unsatisfied(Box<DependencyCheck>),
+    #[cfg(windows)]
     RegistryError(windows::core::Error),
 }
 
@@ -15,8 +29,20 @@ impl Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::Format => write!(f, "invalid format"),
+            Error::HasDependents(dependents) => {
+                write!(f, "provider has {} dependents", dependents.len())
+            }
+            Error::InvalidProvider(key, err) => write!(f, "invalid provider {}: {}", key, err),
+            Error::Io { message, .. } => write!(f, "{}", message),
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
             Error::NotFound => write!(f, "not found"),
             Error::NotSupported => write!(f, "not supported"),
+            Error::Unsatisfied(check) => write!(f, "{}", check),
+            #[cfg(windows)]
             Error::RegistryError(err) => write!(f, "{}", err),
         }
     }
@@ -24,8 +50,74 @@ impl Display for Error {
 
 impl std::error::Error for Error {}
 
+#[cfg(any(feature = "file", feature = "manifest"))]
+impl Error {
+    /// Converts a TOML parse error into an [`Error::Parse`] with its line and column within `s`.
+    pub(crate) fn toml(s: &str, err: toml::de::Error) -> Self {
+        let (line, column) = err
+            .span()
+            .map(|span| position(s, span.start))
+            .unwrap_or_default();
+        Error::Parse {
+            line,
+            column,
+            message: err.message().to_string(),
+        }
+    }
+
+    /// Converts a JSON parse error into an [`Error::Parse`] with its line and column.
+    pub(crate) fn json(err: serde_json::Error) -> Self {
+        // Remove the position serde_json appends to the message.
+        let message = err.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) => message.to_string(),
+            None => message,
+        };
+        Error::Parse {
+            line: err.line(),
+            column: err.column(),
+            message,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::Io {
+            kind: value.kind(),
+            message: value.to_string(),
+        }
+    }
+}
+
+#[cfg(windows)]
 impl From<windows::core::Error> for Error {
     fn from(value: windows::core::Error) -> Self {
         Error::RegistryError(value)
     }
 }
+
+// Gets the 1-based line and column of a byte offset.
+#[cfg(any(feature = "file", feature = "manifest"))]
+fn position(s: &str, offset: usize) -> (usize, usize) {
+    let before = &s[..offset.min(s.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|s| s.chars().count())
+        .unwrap_or_default()
+        + 1;
+    (line, column)
+}
+
+#[cfg(all(test, any(feature = "file", feature = "manifest")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_lines() {
+        assert_eq!(position("abc", 0), (1, 1));
+        assert_eq!(position("abc\ndef", 5), (2, 2));
+    }
+}
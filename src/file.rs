@@ -0,0 +1,565 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::{Error, Result, Scope};
+
+const ROOT_NAME: &str = "Dependencies";
+
+thread_local! {
+    // Lock files held exclusively by the current thread within FileStore::exclusive.
+    static HELD: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The format of files in a [`FileStore`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+/// A [`DependencyStore`] persisted to a directory with one file per scope e.g., `machine.json` and `user.json`.
+///
+/// Each file holds the same tree of keys and values as the registry. Files are locked while read or changed, and
+/// exclusively for the duration of operations like [`register_dependent`](crate::register_dependent) that make several
+/// changes. Changes are written to a temporary file that replaces the original so readers never see partial content.
+/// Files that cannot be parsed return [`Error::Parse`], and because TOML integers are signed, [`Data::QWord`] values
+/// greater than [`i64::MAX`] cannot be set in a [`Format::Toml`] store and return [`Error::NotSupported`].
+#[derive(Clone, Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+    format: Format,
+}
+
+impl FileStore {
+    /// Creates a store in a directory, which is created when a scope is first created.
+    pub fn new(dir: impl Into<PathBuf>, format: Format) -> Self {
+        FileStore {
+            dir: dir.into(),
+            format,
+        }
+    }
+
+    /// Gets the path of the file for a scope.
+    pub fn path(&self, scope: Scope) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", scope, self.format.extension()))
+    }
+
+    // Locks the scope using a separate file, since the data file is replaced when saved. Returns `None` if the current
+    // thread already holds the lock exclusively.
+    fn lock(&self, scope: Scope, exclusive: bool) -> Result<Option<File>> {
+        let path = self.lock_path(scope);
+        if HELD.with_borrow(|held| held.contains(&path)) {
+            return Ok(None);
+        }
+
+        let open = || {
+            OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)
+        };
+        let file = match open() {
+            Err(err) if err.kind() == ErrorKind::NotFound && !exclusive => {
+                return Err(Error::NotFound)
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                fs::create_dir_all(&self.dir)?;
+                open()?
+            }
+            file => file?,
+        };
+
+        if exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(Some(file))
+    }
+
+    fn lock_path(&self, scope: Scope) -> PathBuf {
+        self.dir.join(format!("{}.lock", scope))
+    }
+
+    fn load(&self, scope: Scope) -> Result<Option<Node>> {
+        let s = match fs::read_to_string(self.path(scope)) {
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            s => s?,
+        };
+
+        let node = match self.format {
+            Format::Json => serde_json::from_str(&s).map_err(Error::json)?,
+            Format::Toml => toml::from_str(&s).map_err(|err| Error::toml(&s, err))?,
+        };
+        Ok(Some(node))
+    }
+
+    fn save(&self, scope: Scope, node: &Node) -> Result<()> {
+        let s = match self.format {
+            Format::Json => serde_json::to_string_pretty(node).map_err(|_| Error::Format)?,
+            Format::Toml => toml::to_string_pretty(node).map_err(|_| Error::Format)?,
+        };
+
+        let path = self.path(scope);
+        let temp = path.with_extension(format!("{}.tmp", self.format.extension()));
+        write(&temp, &s)?;
+        fs::rename(&temp, &path)?;
+        Ok(())
+    }
+
+    fn root(&self, scope: Scope) -> FileKey {
+        FileKey {
+            store: self.clone(),
+            scope,
+            path: Vec::new(),
+            name: ROOT_NAME.to_string(),
+        }
+    }
+}
+
+impl DependencyStore for FileStore {
+    type Key = FileKey;
+
+    fn open(&self, scope: Scope) -> Result<FileKey> {
+        let key = self.root(scope);
+        key.with_node(|_| Ok(()))?;
+        Ok(key)
+    }
+
+    fn create(&self, scope: Scope) -> Result<FileKey> {
+        let _lock = self.lock(scope, true)?;
+        if self.load(scope)?.is_none() {
+            self.save(scope, &Node::default())?;
+        }
+
+        Ok(self.root(scope))
+    }
+
+    fn exclusive<T>(&self, scope: Scope, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let Some(_lock) = self.lock(scope, true)? else {
+            return f();
+        };
+
+        let _held = Held::new(self.lock_path(scope));
+        f()
+    }
+}
+
+// Marks a lock file as held by the current thread until dropped.
+struct Held(PathBuf);
+
+impl Held {
+    fn new(path: PathBuf) -> Self {
+        HELD.with_borrow_mut(|held| held.push(path.clone()));
+        Held(path)
+    }
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        HELD.with_borrow_mut(|held| held.retain(|path| path != &self.0));
+    }
+}
+
+/// A key within a [`FileStore`].
+///
+/// The key refers to a path within the file, so operations on a key that was since deleted return [`Error::NotFound`].
+#[derive(Clone, Debug)]
+pub struct FileKey {
+    store: FileStore,
+    scope: Scope,
+    path: Vec<String>,
+    name: String,
+}
+
+impl FileKey {
+    fn subkey(&self, name: &str) -> Self {
+        let mut path = self.path.clone();
+        path.push(name.to_string());
+
+        FileKey {
+            store: self.store.clone(),
+            scope: self.scope,
+            path,
+            name: name.to_string(),
+        }
+    }
+
+    fn with_node<T>(&self, f: impl FnOnce(&Node) -> Result<T>) -> Result<T> {
+        let _lock = self.store.lock(self.scope, false)?;
+        let root = self.store.load(self.scope)?.ok_or(Error::NotFound)?;
+        let node = root.find(&self.path).ok_or(Error::NotFound)?;
+
+        f(node)
+    }
+
+    fn with_node_mut<T>(&self, f: impl FnOnce(&mut Node) -> Result<T>) -> Result<T> {
+        let _lock = self.store.lock(self.scope, true)?;
+        let mut root = self.store.load(self.scope)?.ok_or(Error::NotFound)?;
+        let node = root.find_mut(&self.path).ok_or(Error::NotFound)?;
+
+        let result = f(node)?;
+        self.store.save(self.scope, &root)?;
+        Ok(result)
+    }
+}
+
+impl StoreKey for FileKey {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open_subkey(&self, name: &str) -> Result<Self> {
+        self.with_node(|node| {
+            let (name, _) = find(&node.keys, name).ok_or(Error::NotFound)?;
+            Ok(self.subkey(name))
+        })
+    }
+
+    fn create_subkey(&self, name: &str) -> Result<Self> {
+        self.with_node_mut(|node| {
+            let name = match find(&node.keys, name) {
+                Some((name, _)) => name.clone(),
+                None => {
+                    node.keys.insert(name.to_string(), Node::default());
+                    name.to_string()
+                }
+            };
+            Ok(self.subkey(&name))
+        })
+    }
+
+    fn delete_subkey(&self, name: &str) -> Result<()> {
+        self.with_node_mut(|node| {
+            let (name, _) = find(&node.keys, name).ok_or(Error::NotFound)?;
+            let name = name.clone();
+            node.keys.remove(&name);
+            Ok(())
+        })
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        self.with_node(|node| Ok(node.keys.keys().cloned().collect()))
+    }
+
+    fn values(&self) -> Result<Vec<(Option<String>, Data)>> {
+        self.with_node(|node| {
+            Ok(node
+                .values
+                .iter()
+                .map(|(name, data)| ((!name.is_empty()).then(|| name.clone()), data.clone()))
+                .collect())
+        })
+    }
+
+    fn value(&self, name: Option<&str>) -> Result<Data> {
+        self.with_node(|node| {
+            find(&node.values, name.unwrap_or_default())
+                .map(|(_, data)| data.clone())
+                .ok_or(Error::NotFound)
+        })
+    }
+
+    fn set_value(&self, name: Option<&str>, data: Data) -> Result<()> {
+        // TOML integers are signed 64-bit.
+        if let (Format::Toml, Data::QWord(d)) = (self.store.format, &data) {
+            if i64::try_from(*d).is_err() {
+                return Err(Error::NotSupported);
+            }
+        }
+
+        self.with_node_mut(|node| {
+            let name = name.unwrap_or_default();
+            let name = find(&node.values, name)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_else(|| name.to_string());
+            node.values.insert(name, data);
+            Ok(())
+        })
+    }
+
+    fn delete_value(&self, name: Option<&str>) -> Result<()> {
+        self.with_node_mut(|node| {
+            let (name, _) = find(&node.values, name.unwrap_or_default()).ok_or(Error::NotFound)?;
+            let name = name.clone();
+            node.values.remove(&name);
+            Ok(())
+        })
+    }
+}
+
+// A key keyed by original names, which are compared case-insensitively. The default value has an empty name.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Node {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    values: BTreeMap<String, Data>,
+
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    keys: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn find(&self, path: &[String]) -> Option<&Node> {
+        path.iter()
+            .try_fold(self, |node, name| Some(find(&node.keys, name)?.1))
+    }
+
+    fn find_mut(&mut self, path: &[String]) -> Option<&mut Node> {
+        path.iter().try_fold(self, |node, name| {
+            let upper = name.to_uppercase();
+            node.keys
+                .iter_mut()
+                .find(|(n, _)| n.to_uppercase() == upper)
+                .map(|(_, node)| node)
+        })
+    }
+}
+
+fn find<'a, T>(map: &'a BTreeMap<String, T>, name: &str) -> Option<(&'a String, &'a T)> {
+    let name = name.to_uppercase();
+    map.iter().find(|(n, _)| n.to_uppercase() == name)
+}
+
+fn write(path: &Path, s: &str) -> Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(s.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        check_dependencies, check_dependents, get_provider, register_dependent,
+        unregister_dependent, unregister_provider, Provider, Version,
+    };
+    use std::collections::HashSet;
+
+    fn dir(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("wixpkgdep-file-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    fn reference_counting(format: Format) {
+        let path = dir(format.extension());
+        let store = FileStore::new(&path, format);
+        assert_eq!(store.open(Scope::Machine).unwrap_err(), Error::NotFound);
+
+        Provider {
+            key: "runtime".to_string(),
+            name: "Runtime".to_string(),
+            version: Version::from([1, 2, 3, 4]),
+            ..Default::default()
+        }
+        .register(&store, Scope::Machine)
+        .unwrap();
+        register_dependent(&store, "runtime", "app", Scope::Machine, None, None, None).unwrap();
+        register_dependent(&store, "runtime", "tool", Scope::Machine, None, None, None).unwrap();
+
+        // A new store reads the same files.
+        let store = FileStore::new(&path, format);
+        let provider = get_provider(&store, "RUNTIME", Scope::Machine).unwrap();
+        assert_eq!(provider.name, "Runtime");
+        check_dependencies(
+            &store,
+            "runtime",
+            Scope::Machine,
+            Some(Version::from([1, 0, 0, 0])),
+            None,
+            None,
+            &mut HashSet::new(),
+        )
+        .unwrap();
+
+        unregister_dependent(&store, "runtime", "app", Scope::Machine).unwrap();
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert!(matches!(
+            unregister_provider(&store, "runtime", Scope::Machine, false),
+            Err(Error::HasDependents(_))
+        ));
+
+        unregister_dependent(&store, "runtime", "tool", Scope::Machine).unwrap();
+        unregister_provider(&store, "runtime", Scope::Machine, false).unwrap();
+        assert!(store
+            .open(Scope::Machine)
+            .unwrap()
+            .keys()
+            .unwrap()
+            .is_empty());
+        assert!(store.path(Scope::Machine).exists());
+        assert_eq!(store.open(Scope::User).unwrap_err(), Error::NotFound);
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn reference_counting_json() {
+        reference_counting(Format::Json);
+    }
+
+    #[test]
+    fn reference_counting_toml() {
+        reference_counting(Format::Toml);
+    }
+
+    #[test]
+    fn values() {
+        let path = dir("values");
+        let store = FileStore::new(&path, Format::Json);
+        let key = store
+            .create(Scope::User)
+            .unwrap()
+            .create_subkey("Key")
+            .unwrap();
+        key.set_value(None, Data::String("default".to_string()))
+            .unwrap();
+        key.set_value(Some("Count"), Data::DWord(1)).unwrap();
+        key.set_value(Some("COUNT"), Data::DWord(2)).unwrap();
+
+        assert_eq!(
+            key.values().unwrap(),
+            vec![
+                (None, Data::String("default".to_string())),
+                (Some("Count".to_string()), Data::DWord(2)),
+            ]
+        );
+        assert_eq!(
+            fs::read_to_string(store.path(Scope::User)).unwrap(),
+            r#"{
+  "keys": {
+    "Key": {
+      "values": {
+        "": {
+          "string": "default"
+        },
+        "Count": {
+          "dword": 2
+        }
+      }
+    }
+  }
+}"#
+        );
+
+        key.delete_value(Some("count")).unwrap();
+        assert_eq!(key.value(Some("Count")).unwrap_err(), Error::NotFound);
+
+        store
+            .open(Scope::User)
+            .unwrap()
+            .delete_subkey("KEY")
+            .unwrap();
+        assert_eq!(key.values().unwrap_err(), Error::NotFound);
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn exclusive() {
+        let path = dir("exclusive");
+        let store = FileStore::new(&path, Format::Json);
+        let other = store.clone();
+
+        let handle = store
+            .exclusive(Scope::Machine, || {
+                register_dependent(&store, "runtime", "app", Scope::Machine, None, None, None)?;
+                let handle = std::thread::spawn(move || {
+                    register_dependent(&other, "runtime", "tool", Scope::Machine, None, None, None)
+                });
+
+                // The other thread waits until the lock is released.
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)?;
+                assert_eq!(dependents.unwrap().len(), 1);
+                Ok(handle)
+            })
+            .unwrap();
+        handle.join().unwrap().unwrap();
+
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents.len(), 2);
+        assert!(HELD.with_borrow(Vec::is_empty));
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn qword_toml() {
+        let path = dir("qword");
+        let store = FileStore::new(&path, Format::Toml);
+        let key = store.create(Scope::User).unwrap();
+        key.set_value(Some("Max"), Data::QWord(i64::MAX as u64))
+            .unwrap();
+        assert_eq!(
+            key.set_value(Some("Big"), Data::QWord(u64::MAX))
+                .unwrap_err(),
+            Error::NotSupported
+        );
+        assert_eq!(
+            FileStore::new(&path, Format::Toml)
+                .open(Scope::User)
+                .unwrap()
+                .value(Some("Max"))
+                .unwrap(),
+            Data::QWord(i64::MAX as u64)
+        );
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file() {
+        let path = dir("invalid");
+        fs::create_dir_all(&path).unwrap();
+        let store = FileStore::new(&path, Format::Toml);
+        fs::write(store.path(Scope::Machine), "keys = 1").unwrap();
+        assert!(matches!(
+            store.open(Scope::Machine).unwrap_err(),
+            Error::Parse { line: 1, .. }
+        ));
+
+        let store = FileStore::new(&path, Format::Json);
+        fs::write(store.path(Scope::Machine), "{\n  \"keys\": 1\n}").unwrap();
+        assert_eq!(
+            store.open(Scope::Machine).unwrap_err(),
+            Error::Parse {
+                line: 2,
+                column: 11,
+                message: "invalid type: integer `1`, expected a map".to_string(),
+            }
+        );
+
+        fs::remove_dir_all(path).unwrap();
+    }
+}
@@ -0,0 +1,605 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::store::{DependencyStore, StoreKey};
+use crate::{Dependency, Error, Provider, Result, Scope};
+
+/// A directed graph of providers and the dependents registered for them.
+///
+/// Dependents are typically the provider keys of other packages or bundles, so a dependent may itself be a provider
+/// with dependents. Keys are compared case-insensitively.
+#[derive(Clone, Debug, Default)]
+pub struct DependencyGraph {
+    nodes: BTreeMap<String, Node>,
+
+    // Normalized keys registered as providers that could not be read.
+    unreadable: BTreeSet<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    key: String,
+
+    // Set if the key is registered as a provider.
+    provider: Option<Provider>,
+
+    // Dependents registered for this provider.
+    dependents: Vec<Dependency>,
+
+    // Normalized keys of providers for which this key is registered as a dependent.
+    dependencies: BTreeSet<String>,
+}
+
+impl DependencyGraph {
+    /// Creates an empty graph.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Loads all providers and their dependents registered in a scope.
+    ///
+    /// Providers that cannot be read are added as [`unreadable`](DependencyGraph::unreadable) so that dependents
+    /// registered by them are not considered dangling.
+    pub fn load<S>(store: &S, scope: Scope) -> Result<Self>
+    where
+        S: DependencyStore,
+    {
+        let mut graph = DependencyGraph::new();
+        let key = match store.open(scope) {
+            Err(Error::NotFound) => return Ok(graph),
+            err => err,
+        }?;
+
+        for name in key.keys()? {
+            let provider_key = match key.open_subkey(&name) {
+                Err(Error::NotFound) => continue,
+                err => err,
+            }?;
+
+            match Provider::from(&name, &provider_key) {
+                Ok(provider) => graph.add_provider(provider),
+                Err(_) => graph.add_unreadable(&name),
+            }
+
+            for dependent in crate::read_dependents(&provider_key)?.unwrap_or_default() {
+                graph.add_dependent(&name, dependent);
+            }
+        }
+
+        Ok(graph)
+    }
+
+    /// Adds or replaces a registered provider.
+    pub fn add_provider(&mut self, provider: Provider) {
+        let node = self.node_mut(&provider.key);
+        node.key = provider.key.clone();
+        node.provider = Some(provider);
+    }
+
+    /// Adds the key of a registered provider that could not be read.
+    pub fn add_unreadable(&mut self, key: &str) {
+        self.node_mut(key);
+        self.unreadable.insert(normalize(key));
+    }
+
+    /// Adds or replaces a dependent of a provider, which need not be registered.
+    pub fn add_dependent(&mut self, provider_key: &str, dependent: Dependency) {
+        self.node_mut(&dependent.key)
+            .dependencies
+            .insert(normalize(provider_key));
+
+        let node = self.node_mut(provider_key);
+        match node.dependents.iter_mut().find(|d| **d == dependent) {
+            Some(d) => *d = dependent,
+            None => node.dependents.push(dependent),
+        }
+    }
+
+    /// Gets a registered provider.
+    pub fn provider(&self, key: &str) -> Option<&Provider> {
+        self.nodes.get(&normalize(key))?.provider.as_ref()
+    }
+
+    /// Gets all registered providers.
+    pub fn providers(&self) -> impl Iterator<Item = &Provider> {
+        self.nodes.values().filter_map(|n| n.provider.as_ref())
+    }
+
+    /// Gets the keys of registered providers that could not be read.
+    pub fn unreadable(&self) -> Vec<&str> {
+        self.unreadable.iter().map(|k| self.key(k)).collect()
+    }
+
+    /// Gets the dependents registered directly for a provider.
+    pub fn dependents(&self, key: &str) -> &[Dependency] {
+        self.nodes
+            .get(&normalize(key))
+            .map(|n| n.dependents.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Gets the keys of providers for which a key is registered directly as a dependent.
+    pub fn dependencies(&self, key: &str) -> Vec<&str> {
+        self.nodes
+            .get(&normalize(key))
+            .map(|n| n.dependencies.iter().map(|k| self.key(k)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Gets the keys of all dependents of a provider, including dependents of its dependents.
+    pub fn transitive_dependents(&self, key: &str) -> Vec<&str> {
+        self.walk(key, |node| {
+            node.dependents.iter().map(|d| normalize(&d.key)).collect()
+        })
+    }
+
+    /// Gets the keys of all providers a key depends on, including the dependencies of those providers.
+    pub fn transitive_dependencies(&self, key: &str) -> Vec<&str> {
+        self.walk(key, |node| node.dependencies.iter().cloned().collect())
+    }
+
+    /// Gets the keys in each cycle of dependencies.
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        let mut tarjan = Tarjan::default();
+        for key in self.nodes.keys() {
+            if !tarjan.indices.contains_key(key.as_str()) {
+                tarjan.connect(self, key);
+            }
+        }
+
+        tarjan
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.nodes[c[0]].dependencies.contains(c[0]))
+            .map(|mut c| {
+                c.sort();
+                c.into_iter().map(|k| self.key(k)).collect()
+            })
+            .collect()
+    }
+
+    /// Gets dependents whose own provider key is not registered, along with the key of the provider they depend on.
+    pub fn dangling_dependents(&self) -> Vec<(&str, &Dependency)> {
+        self.nodes
+            .values()
+            .flat_map(|n| n.dependents.iter().map(move |d| (n.key.as_str(), d)))
+            .filter(|(_, d)| !self.is_registered(&normalize(&d.key)))
+            .collect()
+    }
+
+    /// Gets registered providers that have no dependents.
+    pub fn providers_without_dependents(&self) -> Vec<&Provider> {
+        self.nodes
+            .values()
+            .filter(|n| n.dependents.is_empty())
+            .filter_map(|n| n.provider.as_ref())
+            .collect()
+    }
+
+    /// Formats the graph in the Graphviz DOT language.
+    ///
+    /// Edges point from each dependent to the provider it depends on and are labeled with any required version range.
+    /// Keys not registered as providers are drawn with dashed outlines, and unreadable providers without a version.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (id, (key, node)) in self.nodes.iter().enumerate() {
+            let mut label = escape_dot(&node.label());
+            if let Some(provider) = &node.provider {
+                label = format!("{label}\\n{}", provider.version);
+            }
+            let style = if !self.is_registered(key) {
+                ", style=dashed"
+            } else {
+                ""
+            };
+            dot.push_str(&format!("    n{id} [label=\"{label}\"{style}];\n"));
+        }
+        for (provider, dependent, dependency) in self.edges() {
+            dot.push_str(&format!("    n{dependent} -> n{provider}"));
+            if let Some(range) = range(dependency) {
+                dot.push_str(&format!(" [label=\"{}\"]", escape_dot(&range)));
+            }
+            dot.push_str(";\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Formats the graph as a Mermaid flowchart.
+    ///
+    /// Edges point from each dependent to the provider it depends on and are labeled with any required version range.
+    /// Keys not registered as providers are drawn with dashed outlines, and unreadable providers without a version.
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+        for (id, (key, node)) in self.nodes.iter().enumerate() {
+            let mut label = escape_mermaid(&node.label());
+            if let Some(provider) = &node.provider {
+                label = format!("{label}<br/>{}", provider.version);
+            }
+            let class = if !self.is_registered(key) {
+                ":::unregistered"
+            } else {
+                ""
+            };
+            mermaid.push_str(&format!("    n{id}[\"{label}\"]{class}\n"));
+        }
+        for (provider, dependent, dependency) in self.edges() {
+            match range(dependency) {
+                Some(range) => mermaid.push_str(&format!(
+                    "    n{dependent} -->|\"{}\"| n{provider}\n",
+                    escape_mermaid(&range)
+                )),
+                None => mermaid.push_str(&format!("    n{dependent} --> n{provider}\n")),
+            }
+        }
+        mermaid.push_str("    classDef unregistered stroke-dasharray: 5 5\n");
+        mermaid
+    }
+
+    // Gets the node indices of each provider and dependent along with the dependent's registration.
+    fn edges(&self) -> Vec<(usize, usize, &Dependency)> {
+        let ids: HashMap<&str, usize> = self
+            .nodes
+            .keys()
+            .enumerate()
+            .map(|(id, key)| (key.as_str(), id))
+            .collect();
+
+        self.nodes
+            .iter()
+            .flat_map(|(key, node)| {
+                let ids = &ids;
+                node.dependents
+                    .iter()
+                    .map(move |d| (ids[key.as_str()], ids[normalize(&d.key).as_str()], d))
+            })
+            .collect()
+    }
+
+    fn node_mut(&mut self, key: &str) -> &mut Node {
+        self.nodes.entry(normalize(key)).or_insert_with(|| Node {
+            key: key.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn is_registered(&self, normalized: &str) -> bool {
+        self.unreadable.contains(normalized)
+            || self
+                .nodes
+                .get(normalized)
+                .is_some_and(|n| n.provider.is_some())
+    }
+
+    fn key<'a>(&'a self, normalized: &'a str) -> &'a str {
+        self.nodes
+            .get(normalized)
+            .map(|n| n.key.as_str())
+            .unwrap_or(normalized)
+    }
+
+    fn walk(&self, key: &str, next: impl Fn(&Node) -> Vec<String>) -> Vec<&str> {
+        let start = normalize(key);
+        let mut visited = BTreeSet::new();
+        let mut queue = VecDeque::from([start.clone()]);
+
+        while let Some(key) = queue.pop_front() {
+            let Some(node) = self.nodes.get(&key) else {
+                continue;
+            };
+
+            for key in next(node) {
+                if key != start && visited.insert(key.clone()) {
+                    queue.push_back(key);
+                }
+            }
+        }
+
+        visited
+            .iter()
+            .filter_map(|k| self.nodes.get(k))
+            .map(|n| n.key.as_str())
+            .collect()
+    }
+}
+
+impl Node {
+    fn label(&self) -> String {
+        match &self.provider {
+            Some(provider) => provider.to_string(),
+            None => self.key.clone(),
+        }
+    }
+}
+
+// Tarjan's strongly connected components algorithm over dependency edges.
+// cspell:ignore lowlink lowlinks
+#[derive(Default)]
+struct Tarjan<'a> {
+    index: usize,
+    indices: HashMap<&'a str, usize>,
+    lowlinks: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    components: Vec<Vec<&'a str>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn connect(&mut self, graph: &'a DependencyGraph, key: &'a str) {
+        self.indices.insert(key, self.index);
+        self.lowlinks.insert(key, self.index);
+        self.index += 1;
+        self.stack.push(key);
+        self.on_stack.insert(key);
+
+        for next in &graph.nodes[key].dependencies {
+            let next = next.as_str();
+            if !self.indices.contains_key(next) {
+                self.connect(graph, next);
+                let lowlink = self.lowlinks[key].min(self.lowlinks[next]);
+                self.lowlinks.insert(key, lowlink);
+            } else if self.on_stack.contains(next) {
+                let lowlink = self.lowlinks[key].min(self.indices[next]);
+                self.lowlinks.insert(key, lowlink);
+            }
+        }
+
+        if self.lowlinks[key] == self.indices[key] {
+            let mut component = Vec::new();
+            while let Some(k) = self.stack.pop() {
+                self.on_stack.remove(k);
+                component.push(k);
+                if k == key {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
+
+fn normalize(key: &str) -> String {
+    key.to_uppercase()
+}
+
+// Formats the version range of a dependent in interval notation.
+fn range(dependency: &Dependency) -> Option<String> {
+    let range = dependency.range();
+    (!range.is_unbounded()).then(|| range.to_string())
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(value: &str) -> String {
+    value
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Attributes, MemoryStore, Version};
+
+    fn provider(key: &str) -> Provider {
+        Provider {
+            key: key.to_string(),
+            version: Version::from([1, 0, 0, 0]),
+            ..Default::default()
+        }
+    }
+
+    // bundle -> app -> runtime, with tool also depending on runtime.
+    fn graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        for key in ["Bundle", "App", "Runtime", "Tool"] {
+            graph.add_provider(provider(key));
+        }
+        graph.add_dependent("Runtime", Dependency::new("App"));
+        graph.add_dependent("Runtime", Dependency::new("Tool"));
+        graph.add_dependent("App", Dependency::new("Bundle"));
+        graph
+    }
+
+    #[test]
+    fn transitive_dependents() {
+        let graph = graph();
+        assert_eq!(
+            graph.transitive_dependents("runtime"),
+            vec!["App", "Bundle", "Tool"]
+        );
+        assert_eq!(graph.transitive_dependents("App"), vec!["Bundle"]);
+        assert!(graph.transitive_dependents("Bundle").is_empty());
+        assert!(graph.transitive_dependents("missing").is_empty());
+    }
+
+    #[test]
+    fn transitive_dependencies() {
+        let graph = graph();
+        assert_eq!(
+            graph.transitive_dependencies("bundle"),
+            vec!["App", "Runtime"]
+        );
+        assert_eq!(graph.dependencies("Tool"), vec!["Runtime"]);
+        assert!(graph.transitive_dependencies("Runtime").is_empty());
+    }
+
+    #[test]
+    fn cycles() {
+        let mut graph = graph();
+        assert!(graph.cycles().is_empty());
+
+        graph.add_dependent("Bundle", Dependency::new("Runtime"));
+        graph.add_dependent("Tool", Dependency::new("Tool"));
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["App", "Bundle", "Runtime"], vec!["Tool"]]
+        );
+    }
+
+    #[test]
+    fn dangling_dependents() {
+        let mut graph = graph();
+        graph.add_dependent("Runtime", Dependency::new("Removed"));
+
+        let dangling = graph.dangling_dependents();
+        assert_eq!(dangling.len(), 1);
+        assert_eq!(dangling[0].0, "Runtime");
+        assert_eq!(dangling[0].1.key, "Removed");
+    }
+
+    #[test]
+    fn providers_without_dependents() {
+        let graph = graph();
+        let keys: Vec<_> = graph
+            .providers_without_dependents()
+            .into_iter()
+            .map(|p| p.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["Bundle", "Tool"]);
+    }
+
+    #[test]
+    fn add_dependent_replaces() {
+        let mut graph = graph();
+        graph.add_dependent(
+            "Runtime",
+            Dependency {
+                key: "app".to_string(),
+                min_version: Some(Version::from([1, 0, 0, 0])),
+                ..Default::default()
+            },
+        );
+
+        let dependents = graph.dependents("Runtime");
+        assert_eq!(dependents.len(), 2);
+        assert_eq!(dependents[0].min_version, Some(Version::from([1, 0, 0, 0])));
+    }
+
+    #[test]
+    fn load() {
+        let store = MemoryStore::new();
+        provider("Runtime")
+            .register(&store, Scope::Machine)
+            .unwrap();
+        provider("App").register(&store, Scope::Machine).unwrap();
+        crate::register_dependent(&store, "Runtime", "App", Scope::Machine, None, None, None)
+            .unwrap();
+        crate::register_dependent(&store, "App", "Bundle", Scope::Machine, None, None, None)
+            .unwrap();
+
+        let graph = DependencyGraph::load(&store, Scope::Machine).unwrap();
+        assert_eq!(graph.providers().count(), 2);
+        assert_eq!(
+            graph.transitive_dependents("Runtime"),
+            vec!["App", "Bundle"]
+        );
+        assert_eq!(graph.dangling_dependents()[0].1.key, "Bundle");
+
+        // A provider without a version is registered but cannot be read.
+        store
+            .create(Scope::Machine)
+            .unwrap()
+            .create_subkey("Broken")
+            .unwrap();
+        crate::register_dependent(
+            &store,
+            "Runtime",
+            "Broken",
+            Scope::Machine,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        let graph = DependencyGraph::load(&store, Scope::Machine).unwrap();
+        assert_eq!(graph.providers().count(), 2);
+        assert_eq!(graph.provider("broken"), None);
+        assert_eq!(graph.unreadable(), vec!["Broken"]);
+        assert_eq!(graph.dangling_dependents().len(), 1);
+
+        let graph = DependencyGraph::load(&store, Scope::User).unwrap();
+        assert_eq!(graph.providers().count(), 0);
+    }
+
+    fn ranged_graph() -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+        graph.add_provider(Provider {
+            name: "Runtime".to_string(),
+            ..provider("runtime")
+        });
+        graph.add_dependent(
+            "runtime",
+            Dependency {
+                key: "app".to_string(),
+                min_version: Some(Version::from([1, 0, 0, 0])),
+                max_version: Some(Version::from([2, 0, 0, 0])),
+                attributes: Some(Attributes::MIN_VERSION_INCLUSIVE),
+            },
+        );
+        graph.add_dependent("runtime", Dependency::new("\"tool\""));
+        graph
+    }
+
+    #[test]
+    fn to_dot() {
+        assert_eq!(
+            ranged_graph().to_dot(),
+            r#"digraph dependencies {
+    n0 [label="\"tool\"", style=dashed];
+    n1 [label="app", style=dashed];
+    n2 [label="Runtime (runtime)\n1.0.0.0"];
+    n1 -> n2 [label="[1.0.0.0,2.0.0.0)"];
+    n0 -> n2;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn to_dot_unreadable() {
+        let mut graph = DependencyGraph::new();
+        graph.add_unreadable("broken");
+        assert_eq!(
+            graph.to_dot(),
+            "digraph dependencies {\n    n0 [label=\"broken\"];\n}\n"
+        );
+        assert_eq!(
+            graph.to_mermaid().lines().nth(1),
+            Some("    n0[\"broken\"]")
+        );
+    }
+
+    #[test]
+    fn to_mermaid() {
+        assert_eq!(
+            ranged_graph().to_mermaid(),
+            r##"flowchart TD
+    n0["#quot;tool#quot;"]:::unregistered
+    n1["app"]:::unregistered
+    n2["Runtime (runtime)<br/>1.0.0.0"]
+    n1 -->|"[1.0.0.0,2.0.0.0)"| n2
+    n0 --> n2
+    classDef unregistered stroke-dasharray: 5 5
+"##
+        );
+    }
+
+    #[test]
+    fn range_unbounded() {
+        assert_eq!(range(&Dependency::new("test")), None);
+        assert_eq!(
+            range(&Dependency {
+                max_version: Some(Version::from([2, 0, 0, 0])),
+                attributes: Some(Attributes::MAX_VERSION_INCLUSIVE),
+                ..Dependency::new("test")
+            }),
+            Some("(,2.0.0.0]".to_string())
+        );
+    }
+}
@@ -0,0 +1,800 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{path::Path, sync::Arc};
+
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::{Error, Result, Scope};
+
+// cspell:ignore hbin NTUSER regf USRCLASS
+
+// Paths to the dependency tree from the root of the UsrClass.dat, NTUSER.DAT, and SOFTWARE hives.
+const CLASSES_ROOT_PATH: &str = r"Installer\Dependencies";
+const NTUSER_ROOT_PATH: &str = r"Software\Classes\Installer\Dependencies";
+const SOFTWARE_ROOT_PATH: &str = r"Classes\Installer\Dependencies";
+
+// Paths to the Add/Remove Programs registrations from the root of the NTUSER.DAT and SOFTWARE hives, including the
+// 32-bit registry view of the SOFTWARE hive.
+const NTUSER_UNINSTALL_PATH: &str = r"Software\Microsoft\Windows\CurrentVersion\Uninstall";
+const SOFTWARE_UNINSTALL_PATHS: [&str; 2] = [
+    r"Microsoft\Windows\CurrentVersion\Uninstall",
+    r"WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+];
+
+// Offset of the first hive bin following the base block.
+const BINS_OFFSET: usize = 0x1000;
+
+// Flags of key and value cells indicating names are stored as Latin-1 rather than UTF-16LE.
+const KEY_COMP_NAME: u16 = 0x20;
+const VALUE_COMP_NAME: u16 = 0x1;
+
+// Set in the data size of a value cell when the data is stored in the data offset field.
+const DATA_IS_RESIDENT: u32 = 0x8000_0000;
+
+// Data larger than this is stored in segments referenced by a "db" cell.
+const MAX_SEGMENT_SIZE: usize = 16344;
+
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_BINARY: u32 = 3;
+const REG_DWORD: u32 = 4;
+const REG_MULTI_SZ: u32 = 7;
+const REG_QWORD: u32 = 11;
+
+/// A registry hive file in the binary `regf` format e.g., the `SOFTWARE` or `NTUSER.DAT` hive of an offline image.
+///
+/// Hives are parsed only as needed and are never modified. Changes recorded in transaction logs that were not yet
+/// written to the hive file are not read.
+#[derive(Clone, Debug)]
+pub struct Hive {
+    data: Arc<[u8]>,
+}
+
+impl Hive {
+    /// Reads a hive from a file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        Hive::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Creates a hive from the contents of a hive file.
+    ///
+    /// Returns [`Error::Format`] if the data does not start with a valid base block.
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Result<Self> {
+        let data: Vec<u8> = data.into();
+        if data.get(..4) != Some(b"regf") || data.get(BINS_OFFSET..BINS_OFFSET + 4) != Some(b"hbin")
+        {
+            return Err(Error::Format);
+        }
+
+        Ok(Hive { data: data.into() })
+    }
+
+    /// Opens the root key of the hive.
+    fn root(&self) -> Result<HiveKey> {
+        let offset = read_u32(&self.data, 0x24)?;
+        let cell = KeyCell::read(&self.data, offset)?;
+        Ok(HiveKey {
+            hive: self.data.clone(),
+            name: cell.name()?,
+            offset,
+        })
+    }
+
+    // Opens the key at a path within the hive.
+    fn find(&self, path: &str) -> Result<HiveKey> {
+        path.split('\\')
+            .try_fold(self.root()?, |key, name| key.open_subkey(name))
+    }
+}
+
+/// A read-only [`DependencyStore`] backed by offline registry hive files.
+///
+/// The user scope is read from the `UsrClass.dat` hive, where per-user classes including the dependency tree are
+/// stored, or else from the `Software\Classes` key of the `NTUSER.DAT` hive. Per-user products registered with
+/// Add/Remove Programs are read from the `NTUSER.DAT` hive. The machine scope is read from a `SOFTWARE` hive. Any
+/// operation that would change a hive returns [`Error::NotSupported`].
+#[derive(Clone, Debug, Default)]
+pub struct HiveStore {
+    classes: Option<Hive>,
+    ntuser: Option<Hive>,
+    machine: Option<Hive>,
+}
+
+impl HiveStore {
+    /// Creates a store from optional `UsrClass.dat` and `NTUSER.DAT` hives of a user and `SOFTWARE` hive of the machine.
+    pub fn new(classes: Option<Hive>, ntuser: Option<Hive>, machine: Option<Hive>) -> Self {
+        HiveStore {
+            classes,
+            ntuser,
+            machine,
+        }
+    }
+
+    // Opens the first key found at a path within one of the given hives.
+    fn find(hives: &[(&Option<Hive>, &str)]) -> Result<HiveKey> {
+        for (hive, path) in hives {
+            let Some(hive) = hive else {
+                continue;
+            };
+            match hive.find(path) {
+                Err(Error::NotFound) => continue,
+                key => return key,
+            }
+        }
+
+        Err(Error::NotFound)
+    }
+}
+
+impl DependencyStore for HiveStore {
+    type Key = HiveKey;
+
+    fn open(&self, scope: Scope) -> Result<HiveKey> {
+        match scope {
+            Scope::User => HiveStore::find(&[
+                (&self.classes, CLASSES_ROOT_PATH),
+                (&self.ntuser, NTUSER_ROOT_PATH),
+            ]),
+            Scope::Machine => HiveStore::find(&[(&self.machine, SOFTWARE_ROOT_PATH)]),
+        }
+    }
+
+    fn create(&self, _scope: Scope) -> Result<HiveKey> {
+        Err(Error::NotSupported)
+    }
+
+    fn open_uninstall(&self, scope: Scope) -> Result<Vec<HiveKey>> {
+        let paths = match scope {
+            Scope::User => vec![(&self.ntuser, NTUSER_UNINSTALL_PATH)],
+            Scope::Machine => SOFTWARE_UNINSTALL_PATHS
+                .iter()
+                .map(|path| (&self.machine, *path))
+                .collect(),
+        };
+
+        let mut keys = Vec::new();
+        for path in paths {
+            match HiveStore::find(&[path]) {
+                Err(Error::NotFound) => continue,
+                key => keys.push(key?),
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// A key within a [`Hive`].
+#[derive(Clone, Debug)]
+pub struct HiveKey {
+    hive: Arc<[u8]>,
+    name: String,
+    offset: u32,
+}
+
+impl HiveKey {
+    fn subkeys(&self) -> Result<Vec<(String, u32)>> {
+        let cell = KeyCell::read(&self.hive, self.offset)?;
+        let mut offsets = Vec::with_capacity(cell.subkey_count as usize);
+        if cell.subkey_count > 0 {
+            read_subkey_list(&self.hive, cell.subkeys, &mut offsets, 0)?;
+        }
+
+        offsets
+            .into_iter()
+            .map(|offset| Ok((KeyCell::read(&self.hive, offset)?.name()?, offset)))
+            .collect()
+    }
+
+    fn value_cells(&self) -> Result<Vec<ValueCell<'_>>> {
+        let cell = KeyCell::read(&self.hive, self.offset)?;
+        if cell.value_count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let list = read_cell(&self.hive, cell.values)?;
+        (0..cell.value_count as usize)
+            .map(|i| ValueCell::read(&self.hive, read_u32(list, i * 4)?))
+            .collect()
+    }
+}
+
+impl StoreKey for HiveKey {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open_subkey(&self, name: &str) -> Result<Self> {
+        let name = name.to_uppercase();
+        self.subkeys()?
+            .into_iter()
+            .find(|(n, _)| n.to_uppercase() == name)
+            .map(|(name, offset)| HiveKey {
+                hive: self.hive.clone(),
+                name,
+                offset,
+            })
+            .ok_or(Error::NotFound)
+    }
+
+    fn create_subkey(&self, _name: &str) -> Result<Self> {
+        Err(Error::NotSupported)
+    }
+
+    fn delete_subkey(&self, _name: &str) -> Result<()> {
+        Err(Error::NotSupported)
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        Ok(self.subkeys()?.into_iter().map(|(name, _)| name).collect())
+    }
+
+    fn values(&self) -> Result<Vec<(Option<String>, Data)>> {
+        let mut values = Vec::new();
+        for cell in self.value_cells()? {
+            // Skip values with types that cannot be represented.
+            if let Some(data) = cell.data()? {
+                let name = cell.name()?;
+                values.push(((!name.is_empty()).then_some(name), data));
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn value(&self, name: Option<&str>) -> Result<Data> {
+        let name = name.unwrap_or_default().to_uppercase();
+        for cell in self.value_cells()? {
+            if cell.name()?.to_uppercase() == name {
+                return cell.data()?.ok_or(Error::Format);
+            }
+        }
+
+        Err(Error::NotFound)
+    }
+
+    fn set_value(&self, _name: Option<&str>, _data: Data) -> Result<()> {
+        Err(Error::NotSupported)
+    }
+
+    fn delete_value(&self, _name: Option<&str>) -> Result<()> {
+        Err(Error::NotSupported)
+    }
+}
+
+// A key node ("nk") cell.
+struct KeyCell<'a> {
+    flags: u16,
+    subkey_count: u32,
+    subkeys: u32,
+    value_count: u32,
+    values: u32,
+    name: &'a [u8],
+}
+
+impl<'a> KeyCell<'a> {
+    fn read(hive: &'a [u8], offset: u32) -> Result<Self> {
+        let cell = read_cell(hive, offset)?;
+        if cell.get(..2) != Some(b"nk") {
+            return Err(Error::Format);
+        }
+
+        let name_len = read_u16(cell, 0x48)? as usize;
+        Ok(KeyCell {
+            flags: read_u16(cell, 0x02)?,
+            subkey_count: read_u32(cell, 0x14)?,
+            subkeys: read_u32(cell, 0x1c)?,
+            value_count: read_u32(cell, 0x24)?,
+            values: read_u32(cell, 0x28)?,
+            name: cell.get(0x4c..0x4c + name_len).ok_or(Error::Format)?,
+        })
+    }
+
+    fn name(&self) -> Result<String> {
+        decode_name(self.name, self.flags & KEY_COMP_NAME != 0)
+    }
+}
+
+// A key value ("vk") cell.
+struct ValueCell<'a> {
+    hive: &'a [u8],
+    flags: u16,
+    size: u32,
+    offset: u32,
+    data_type: u32,
+    name: &'a [u8],
+}
+
+impl<'a> ValueCell<'a> {
+    fn read(hive: &'a [u8], offset: u32) -> Result<Self> {
+        let cell = read_cell(hive, offset)?;
+        if cell.get(..2) != Some(b"vk") {
+            return Err(Error::Format);
+        }
+
+        let name_len = read_u16(cell, 0x02)? as usize;
+        Ok(ValueCell {
+            hive,
+            flags: read_u16(cell, 0x10)?,
+            size: read_u32(cell, 0x04)?,
+            offset: read_u32(cell, 0x08)?,
+            data_type: read_u32(cell, 0x0c)?,
+            name: cell.get(0x14..0x14 + name_len).ok_or(Error::Format)?,
+        })
+    }
+
+    fn name(&self) -> Result<String> {
+        decode_name(self.name, self.flags & VALUE_COMP_NAME != 0)
+    }
+
+    fn bytes(&self) -> Result<Vec<u8>> {
+        if self.size & DATA_IS_RESIDENT != 0 {
+            let size = (self.size & !DATA_IS_RESIDENT) as usize;
+            let data = self.offset.to_le_bytes();
+            return Ok(data.get(..size).ok_or(Error::Format)?.to_vec());
+        }
+
+        let size = self.size as usize;
+        let cell = read_cell(self.hive, self.offset)?;
+        if size > MAX_SEGMENT_SIZE && cell.get(..2) == Some(b"db") {
+            let count = read_u16(cell, 0x02)? as usize;
+            let list = read_cell(self.hive, read_u32(cell, 0x04)?)?;
+            let mut data = Vec::with_capacity(size);
+            for i in 0..count {
+                let segment = read_cell(self.hive, read_u32(list, i * 4)?)?;
+                let len = segment.len().min(MAX_SEGMENT_SIZE);
+                data.extend_from_slice(&segment[..len]);
+            }
+            data.truncate(size);
+            return match data.len() {
+                len if len == size => Ok(data),
+                _ => Err(Error::Format),
+            };
+        }
+
+        Ok(cell.get(..size).ok_or(Error::Format)?.to_vec())
+    }
+
+    // Gets the data, or `None` if the data type is not supported.
+    fn data(&self) -> Result<Option<Data>> {
+        let data = self.bytes()?;
+        Ok(Some(match self.data_type {
+            REG_BINARY => Data::Binary(data),
+            REG_DWORD => Data::DWord(u32::from_le_bytes(
+                data.try_into().map_err(|_| Error::Format)?,
+            )),
+            REG_QWORD => Data::QWord(u64::from_le_bytes(
+                data.try_into().map_err(|_| Error::Format)?,
+            )),
+            REG_SZ | REG_EXPAND_SZ => {
+                let data = decode_utf16(&data);
+                let data = data.split(|&c| c == 0).next().unwrap_or_default();
+                Data::String(String::from_utf16_lossy(data))
+            }
+            REG_MULTI_SZ => Data::MultiString(
+                decode_utf16(&data)
+                    .split(|&c| c == 0)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from_utf16_lossy)
+                    .collect(),
+            ),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+// Collects the key cell offsets from a subkey list, following index roots ("ri") to their leaves.
+fn read_subkey_list(hive: &[u8], offset: u32, offsets: &mut Vec<u32>, depth: usize) -> Result<()> {
+    let cell = read_cell(hive, offset)?;
+    let count = read_u16(cell, 0x02)? as usize;
+    match cell.get(..2) {
+        // Fast and hash leaves contain an offset and a hint or hash for each key.
+        Some(b"lf") | Some(b"lh") => {
+            for i in 0..count {
+                offsets.push(read_u32(cell, 4 + i * 8)?);
+            }
+        }
+        Some(b"li") => {
+            for i in 0..count {
+                offsets.push(read_u32(cell, 4 + i * 4)?);
+            }
+        }
+        // Index roots only reference leaves.
+        Some(b"ri") if depth == 0 => {
+            for i in 0..count {
+                read_subkey_list(hive, read_u32(cell, 4 + i * 4)?, offsets, depth + 1)?;
+            }
+        }
+        _ => return Err(Error::Format),
+    }
+
+    Ok(())
+}
+
+// Gets the data of an allocated cell at an offset relative to the first hive bin.
+fn read_cell(hive: &[u8], offset: u32) -> Result<&[u8]> {
+    let start = BINS_OFFSET
+        .checked_add(offset as usize)
+        .ok_or(Error::Format)?;
+    let size = read_u32(hive, start)? as i32;
+
+    // Allocated cells have a negative size that includes the size field.
+    if size >= -4 {
+        return Err(Error::Format);
+    }
+    hive.get(start + 4..start + size.unsigned_abs() as usize)
+        .ok_or(Error::Format)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(Error::Format)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(Error::Format)
+}
+
+fn decode_utf16(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect()
+}
+
+fn decode_name(name: &[u8], compressed: bool) -> Result<String> {
+    if compressed {
+        return Ok(name.iter().map(|&c| c as char).collect());
+    }
+
+    String::from_utf16(&decode_utf16(name)).map_err(|_| Error::Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_dependents, get_provider, Provider, ProviderOwner, Version};
+
+    // A key to write to a test hive.
+    struct TestKey {
+        name: &'static str,
+        values: Vec<(&'static str, u32, Vec<u8>)>,
+        keys: Vec<TestKey>,
+    }
+
+    fn key(
+        name: &'static str,
+        values: Vec<(&'static str, u32, Vec<u8>)>,
+        keys: Vec<TestKey>,
+    ) -> TestKey {
+        TestKey { name, values, keys }
+    }
+
+    fn sz(s: &str) -> Vec<u8> {
+        s.encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    // Writes a minimal hive with a single bin.
+    fn hive(root: &TestKey) -> Vec<u8> {
+        let mut bins = vec![0u8; 0x20];
+        let root = write_key(&mut bins, root);
+
+        let size = bins.len().next_multiple_of(0x1000);
+        bins.resize(size, 0);
+        bins[..4].copy_from_slice(b"hbin");
+        bins[8..12].copy_from_slice(&(size as u32).to_le_bytes());
+
+        let mut data = vec![0u8; BINS_OFFSET];
+        data[..4].copy_from_slice(b"regf");
+        data[0x24..0x28].copy_from_slice(&root.to_le_bytes());
+        data.extend(bins);
+        data
+    }
+
+    fn write_cell(bins: &mut Vec<u8>, data: &[u8]) -> u32 {
+        let offset = bins.len() as u32;
+        let size = (data.len() + 4).next_multiple_of(8);
+        bins.extend((-(size as i32)).to_le_bytes());
+        bins.extend(data);
+        bins.resize(offset as usize + size, 0);
+        offset
+    }
+
+    fn write_key(bins: &mut Vec<u8>, key: &TestKey) -> u32 {
+        let mut values = Vec::new();
+        for (name, data_type, data) in &key.values {
+            let (size, offset) = if data.len() <= 4 {
+                let mut inline = [0u8; 4];
+                inline[..data.len()].copy_from_slice(data);
+                (
+                    data.len() as u32 | DATA_IS_RESIDENT,
+                    u32::from_le_bytes(inline),
+                )
+            } else if data.len() > MAX_SEGMENT_SIZE {
+                let segments: Vec<u8> = data
+                    .chunks(MAX_SEGMENT_SIZE)
+                    .flat_map(|c| write_cell(bins, c).to_le_bytes())
+                    .collect();
+                let list = write_cell(bins, &segments);
+                let mut db = b"db".to_vec();
+                db.extend((data.len().div_ceil(MAX_SEGMENT_SIZE) as u16).to_le_bytes());
+                db.extend(list.to_le_bytes());
+                (data.len() as u32, write_cell(bins, &db))
+            } else {
+                (data.len() as u32, write_cell(bins, data))
+            };
+
+            let mut vk = b"vk".to_vec();
+            vk.extend((name.len() as u16).to_le_bytes());
+            vk.extend(size.to_le_bytes());
+            vk.extend(offset.to_le_bytes());
+            vk.extend(data_type.to_le_bytes());
+            vk.extend(VALUE_COMP_NAME.to_le_bytes());
+            vk.extend([0, 0]);
+            vk.extend(name.as_bytes());
+            values.extend(write_cell(bins, &vk).to_le_bytes());
+        }
+
+        let mut list = b"lh".to_vec();
+        list.extend((key.keys.len() as u16).to_le_bytes());
+        for subkey in &key.keys {
+            list.extend(write_key(bins, subkey).to_le_bytes());
+            list.extend(0u32.to_le_bytes());
+        }
+
+        let subkeys = write_cell(bins, &list);
+        let values = write_cell(bins, &values);
+
+        // Store names as UTF-16LE when they are not ASCII.
+        let (flags, name) = if key.name.is_ascii() {
+            (KEY_COMP_NAME, key.name.as_bytes().to_vec())
+        } else {
+            (
+                0,
+                key.name.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            )
+        };
+
+        let mut nk = vec![0u8; 0x4c];
+        nk[..2].copy_from_slice(b"nk");
+        nk[0x02..0x04].copy_from_slice(&flags.to_le_bytes());
+        nk[0x14..0x18].copy_from_slice(&(key.keys.len() as u32).to_le_bytes());
+        nk[0x1c..0x20].copy_from_slice(&subkeys.to_le_bytes());
+        nk[0x24..0x28].copy_from_slice(&(key.values.len() as u32).to_le_bytes());
+        nk[0x28..0x2c].copy_from_slice(&values.to_le_bytes());
+        nk[0x48..0x4a].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        nk.extend(name);
+        write_cell(bins, &nk)
+    }
+
+    // A SOFTWARE hive with a provider and dependent.
+    fn software() -> Hive {
+        let bundle = key("bundle", vec![("MinVersion", REG_SZ, sz("1.0"))], vec![]);
+        let runtime = key(
+            "Runtime",
+            vec![
+                ("", REG_SZ, sz("{F8E9F4B1-0000-0000-0000-000000000000}")),
+                ("DisplayName", REG_SZ, sz("Runtime")),
+                ("Version", REG_SZ, sz("1.2.3.4")),
+                ("Attributes", REG_DWORD, 0x10000u32.to_le_bytes().to_vec()),
+                ("Large", REG_BINARY, vec![7u8; 20000]),
+                (
+                    "Multi",
+                    REG_MULTI_SZ,
+                    [sz("a"), sz("b"), vec![0, 0]].concat(),
+                ),
+            ],
+            vec![key("Dependents", vec![], vec![bundle])],
+        );
+        let mut root = key(
+            "ROOT",
+            vec![],
+            vec![key(
+                "Classes",
+                vec![],
+                vec![key(
+                    "Installer",
+                    vec![],
+                    vec![key(
+                        "Dependencies",
+                        vec![],
+                        vec![runtime, key("Ünicode", vec![], vec![])],
+                    )],
+                )],
+            )],
+        );
+        let product = key(
+            "{F8E9F4B1-0000-0000-0000-000000000000}",
+            vec![("DisplayName", REG_SZ, sz("Example Runtime"))],
+            vec![],
+        );
+        let uninstall = |product| {
+            ["Microsoft", "Windows", "CurrentVersion", "Uninstall"]
+                .into_iter()
+                .rev()
+                .fold(vec![product], |keys, name| vec![key(name, vec![], keys)])
+        };
+        let wow64 = key(
+            "{E0000000-0000-0000-0000-000000000000}",
+            vec![("DisplayName", REG_SZ, sz("Example Tools (x86)"))],
+            vec![],
+        );
+        root.keys.extend(uninstall(product));
+        root.keys.push(key("WOW6432Node", vec![], uninstall(wow64)));
+
+        Hive::from_bytes(hive(&root)).unwrap()
+    }
+
+    #[test]
+    fn get_provider_from_hive() {
+        let store = HiveStore::new(None, None, Some(software()));
+        let provider = get_provider(&store, "runtime", Scope::Machine).unwrap();
+        assert_eq!(provider.key, "runtime");
+        assert_eq!(provider.name, "Runtime");
+        assert_eq!(provider.version, Version::from([1, 2, 3, 4]));
+        assert_eq!(
+            provider.id.as_deref(),
+            Some("{F8E9F4B1-0000-0000-0000-000000000000}")
+        );
+        assert_eq!(provider.attributes, Some(crate::Attributes::BUNDLE));
+
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents[0].key, "bundle");
+        assert_eq!(dependents[0].min_version, Some(Version::from([1, 0, 0, 0])));
+
+        assert_eq!(
+            get_provider(&store, "runtime", Scope::User).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn keys_and_values() {
+        let root = HiveStore::new(None, None, Some(software()))
+            .open(Scope::Machine)
+            .unwrap();
+        assert_eq!(root.name(), "Dependencies");
+        assert_eq!(root.keys().unwrap(), vec!["Runtime", "Ünicode"]);
+        assert_eq!(root.open_subkey("ünicode").unwrap().name(), "Ünicode");
+
+        let key = root.open_subkey("runtime").unwrap();
+        assert_eq!(key.values().unwrap().len(), 6);
+        assert_eq!(
+            key.value(Some("large")).unwrap(),
+            Data::Binary(vec![7u8; 20000])
+        );
+        assert_eq!(
+            key.value(Some("Multi")).unwrap(),
+            Data::MultiString(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(key.value(Some("Missing")).unwrap_err(), Error::NotFound);
+    }
+
+    #[test]
+    fn open_uninstall() {
+        let store = HiveStore::new(None, None, Some(software()));
+        let uninstall = store.open_uninstall(Scope::Machine).unwrap();
+        assert_eq!(uninstall.len(), 2);
+        assert_eq!(uninstall[0].name(), "Uninstall");
+        assert_eq!(
+            uninstall[0]
+                .open_subkey("{f8e9f4b1-0000-0000-0000-000000000000}")
+                .unwrap()
+                .value(Some("DisplayName"))
+                .unwrap(),
+            Data::String("Example Runtime".to_string())
+        );
+        assert_eq!(
+            uninstall[1].keys().unwrap(),
+            vec!["{E0000000-0000-0000-0000-000000000000}"]
+        );
+        assert!(store.open_uninstall(Scope::User).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_only() {
+        let store = HiveStore::new(None, None, Some(software()));
+        let provider = Provider {
+            key: "tools".to_string(),
+            version: Version::from([1, 0, 0, 0]),
+            ..Default::default()
+        };
+        assert_eq!(
+            provider.register(&store, Scope::Machine).unwrap_err(),
+            Error::NotSupported
+        );
+
+        let root = store.open(Scope::Machine).unwrap();
+        assert_eq!(
+            root.delete_subkey("runtime").unwrap_err(),
+            Error::NotSupported
+        );
+    }
+
+    // A key nested within the keys at a path.
+    fn nested(path: &'static str, leaf: TestKey) -> TestKey {
+        let mut keys = path
+            .rsplit('\\')
+            .fold(vec![leaf], |keys, name| vec![key(name, vec![], keys)]);
+        keys.remove(0)
+    }
+
+    #[test]
+    fn user_hives() {
+        let tools = key(
+            "tools",
+            vec![
+                ("", REG_SZ, sz("{C0000000-0000-0000-0000-000000000000}")),
+                ("Version", REG_SZ, sz("2.0")),
+            ],
+            vec![],
+        );
+        let classes = key(
+            "ROOT",
+            vec![],
+            vec![nested(r"Installer\Dependencies", tools)],
+        );
+        let product = key(
+            "{C0000000-0000-0000-0000-000000000000}",
+            vec![("DisplayName", REG_SZ, sz("Example Tools"))],
+            vec![],
+        );
+        let ntuser = key(
+            "ROOT",
+            vec![],
+            vec![key(
+                "Software",
+                vec![],
+                vec![
+                    nested(
+                        r"Classes\Installer\Dependencies",
+                        key("legacy", vec![], vec![]),
+                    ),
+                    nested(r"Microsoft\Windows\CurrentVersion\Uninstall", product),
+                ],
+            )],
+        );
+        let classes = Hive::from_bytes(hive(&classes)).unwrap();
+        let ntuser = Hive::from_bytes(hive(&ntuser)).unwrap();
+
+        // The dependency tree is read from UsrClass.dat before NTUSER.DAT, and products only from NTUSER.DAT.
+        let store = HiveStore::new(Some(classes), Some(ntuser.clone()), None);
+        let owner = ProviderOwner::get(&store, "tools", Scope::User).unwrap();
+        assert_eq!(owner.provider.version, Version::from([2, 0, 0, 0]));
+        assert_eq!(
+            owner.entry.unwrap().display_name.as_deref(),
+            Some("Example Tools")
+        );
+
+        let store = HiveStore::new(None, Some(ntuser), None);
+        assert_eq!(
+            store.open(Scope::User).unwrap().keys().unwrap(),
+            vec!["legacy"]
+        );
+        assert_eq!(
+            HiveStore::new(None, None, Some(software()))
+                .open(Scope::User)
+                .unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn invalid_hive() {
+        assert_eq!(
+            Hive::from_bytes(b"regf".to_vec()).unwrap_err(),
+            Error::Format
+        );
+
+        let mut data = hive(&key("ROOT", vec![], vec![]));
+        data[0x24..0x28].copy_from_slice(&0xffffu32.to_le_bytes());
+        let store = HiveStore::new(Some(Hive::from_bytes(data).unwrap()), None, None);
+        assert_eq!(store.open(Scope::User).unwrap_err(), Error::Format);
+    }
+}
@@ -1,32 +1,67 @@
 // Copyright 2023 Heath Stewart.
 // Licensed under the MIT License. See LICENSE.txt in the project root for license information.
 
-use std::{
-    collections::HashSet,
-    fmt::Display,
-    ops::{BitAnd, BitOr},
-    str::FromStr,
-};
-
-use windows::{
-    core::{w, PCWSTR},
-    Win32::System::Registry::HKEY,
-};
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 
+mod attributes;
+#[cfg(feature = "wix")]
+mod bundle;
+mod check;
 mod error;
+#[cfg(feature = "file")]
+mod file;
+mod graph;
+mod hive;
+#[cfg(feature = "manifest")]
+mod manifest;
+mod memory;
+#[cfg(feature = "msi")]
+mod package;
 mod provider;
+mod regfile;
+#[cfg(windows)]
 mod registry;
+mod requirements;
+mod snapshot;
+mod stale;
+mod store;
+mod uninstall;
 mod version;
-
+#[cfg(feature = "wine")]
+mod wine;
+#[cfg(feature = "wix")]
+mod wxs;
+
+pub use attributes::Attributes;
+#[cfg(feature = "wix")]
+pub use bundle::{BundleManifest, BundlePackage, RelatedBundle, RelatedBundleAction};
+pub use check::{DependencyCheck, Unsatisfied};
 pub use error::Error;
-pub use provider::{Dependency, Provider};
-pub use version::Version;
-
-use registry::map_registry_error;
+#[cfg(feature = "file")]
+pub use file::{FileKey, FileStore, Format};
+pub use graph::DependencyGraph;
+pub use hive::{Hive, HiveKey, HiveStore};
+pub use memory::{MemoryKey, MemoryStore};
+#[cfg(feature = "msi")]
+pub use package::{MsiPackage, PackageProvider};
+pub use provider::{Dependency, Provider, Providers};
+pub use regfile::{export_reg, import_reg};
+#[cfg(windows)]
+pub use registry::WindowsRegistry;
+pub use requirements::{Report, Requirement, Requirements};
+pub use snapshot::{Change, Diff, Snapshot, SnapshotEntry};
+pub use stale::{find_stale_dependents, prune, Pruned, StaleDependent};
+pub use store::{Data, DependencyStore, StoreKey};
+pub use uninstall::{ProviderOwner, UninstallEntry};
+pub use version::{Version, VersionRange};
+#[cfg(feature = "wine")]
+pub use wine::{WineKey, WineRegistry};
+#[cfg(feature = "wix")]
+pub use wxs::{AuthoredDependency, AuthoredProvider, WixAuthoring, PRODUCT_CODE_KEY};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Scope {
     User,
 
@@ -34,37 +69,77 @@ pub enum Scope {
     Machine,
 }
 
-#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
-#[repr(u32)]
-pub enum Attributes {
+/// Selects the scopes in which to look for providers and dependents.
+///
+/// A [`Scope`] converts to a selector for only that scope.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ScopeSelector {
+    /// Only the user scope.
+    User,
+
+    /// Only the machine scope.
     #[default]
-    None,
+    Machine,
+
+    /// Both scopes, looking in the given scope first.
+    Both(Scope),
+}
 
-    MinVersionInclusive = 0x100,
-    MaxVersionInclusive = 0x200,
+impl ScopeSelector {
+    /// Gets the selected scopes in order of precedence.
+    pub fn scopes(self) -> &'static [Scope] {
+        match self {
+            ScopeSelector::User => &[Scope::User],
+            ScopeSelector::Machine => &[Scope::Machine],
+            ScopeSelector::Both(Scope::User) => &[Scope::User, Scope::Machine],
+            ScopeSelector::Both(Scope::Machine) => &[Scope::Machine, Scope::User],
+        }
+    }
+}
+
+impl From<Scope> for ScopeSelector {
+    fn from(value: Scope) -> Self {
+        match value {
+            Scope::User => ScopeSelector::User,
+            Scope::Machine => ScopeSelector::Machine,
+        }
+    }
 }
 
-const ROOT_KEY: PCWSTR = w!("Software\\Classes\\Installer\\Dependencies");
-const DEPENDENTS_KEY: PCWSTR = w!("Dependents");
+const DEPENDENTS_KEY: &str = "Dependents";
 
 /// Gets information about a provider.
-pub fn get_provider<K>(provider_key: K, scope: Scope) -> Result<Provider>
+pub fn get_provider<S, K>(store: &S, provider_key: K, scope: Scope) -> Result<Provider>
 where
+    S: DependencyStore,
     K: AsRef<str> + Into<String>,
 {
-    let key =
-        registry::Key::open::<HKEY, PCWSTR>(scope.into(), ROOT_KEY).map_err(map_registry_error)?;
-
-    let _provider_key = to_pcwstr(provider_key.as_ref());
-    let key = key
-        .open_subkey::<PCWSTR>(_provider_key)
-        .map_err(map_registry_error)?;
+    let key = store.open(scope)?;
+    let key = key.open_subkey(provider_key.as_ref())?;
 
     Provider::from(provider_key, &key)
 }
 
+/// Gets all providers registered in a scope.
+///
+/// Providers that cannot be read are yielded as errors without ending the iteration.
+/// No providers are yielded if the root key for the scope does not exist.
+pub fn providers<S>(store: &S, scope: Scope) -> Result<Providers<S::Key>>
+where
+    S: DependencyStore,
+{
+    match store.open(scope) {
+        Ok(key) => Providers::new(Some(key)),
+        Err(Error::NotFound) => Providers::new(None),
+        Err(err) => Err(err),
+    }
+}
+
 /// Checks that the dependency is registered and within the requested version range.
-pub fn check_dependencies<K>(
+///
+/// Returns [`Error::Unsatisfied`] describing why the dependency is not satisfied, and adds the dependency to `dependencies`.
+pub fn check_dependencies<S, K>(
+    store: &S,
     provider_key: K,
     scope: Scope,
     min_version: Option<Version>,
@@ -73,129 +148,305 @@ pub fn check_dependencies<K>(
     dependencies: &mut HashSet<Dependency>,
 ) -> Result<()>
 where
+    S: DependencyStore,
     K: AsRef<str> + Into<String>,
 {
-    // Equivalent to deputil:DepCheckDependency.
-    let key = registry::Key::open::<HKEY, PCWSTR>(scope.into(), ROOT_KEY)
-        .map_err(Error::RegistryError)?;
-
-    // If the key or its Version value is missing, add it to the set of dependencies, and return NotFound.
-    let _provider_key = to_pcwstr(provider_key.as_ref());
-    let version: Version;
-    match key
-        .open_subkey::<PCWSTR>(_provider_key)
-        .map_err(map_registry_error)
-    {
-        Ok(k) => {
-            if let Ok(_version) = key.value(w!("Version")).map(|v| v.to_version())? {
-                version = _version;
-            } else {
-                // We only have the provider key at this time.
-                dependencies.insert(Dependency::new(provider_key));
-                return Err(Error::NotFound);
-            }
-            k
-        }
-        Err(Error::NotFound) => {
-            // We only have the provider key at this time.
-            dependencies.insert(Dependency::new(provider_key));
-            return Err(Error::NotFound);
-        }
-        Err(err) => return Err(err),
-    };
-
-    // Since the provider and Version were found, check the version range requirements.
-    let dependency = Dependency::new(provider_key);
-    if let Some(min_version) = min_version {
-        let allow_equal = (attributes.unwrap_or_default() & Attributes::MinVersionInclusive)
-            == Attributes::MinVersionInclusive as u32;
+    check_dependencies_in(
+        store,
+        provider_key,
+        scope.into(),
+        min_version,
+        max_version,
+        attributes,
+        dependencies,
+    )
+}
 
-        if !(allow_equal && min_version <= version || min_version < version) {
-            dependencies.insert(dependency);
-            return Err(Error::NotFound);
-        }
+/// Checks that the dependency is registered in any of the selected scopes and within the requested version range.
+///
+/// See [`check_dependencies`] and [`check_dependency_in`].
+pub fn check_dependencies_in<S, K>(
+    store: &S,
+    provider_key: K,
+    scopes: ScopeSelector,
+    min_version: Option<Version>,
+    max_version: Option<Version>,
+    attributes: Option<Attributes>,
+    dependencies: &mut HashSet<Dependency>,
+) -> Result<()>
+where
+    S: DependencyStore,
+    K: AsRef<str> + Into<String>,
+{
+    let range = VersionRange::new(min_version, max_version, attributes.unwrap_or_default());
+    let check = check_dependency_in(store, provider_key.as_ref(), scopes, range)?;
+    if !check.is_satisfied() {
+        dependencies.insert(Dependency {
+            key: provider_key.into(),
+            min_version,
+            max_version,
+            attributes,
+        });
+        return Err(Error::Unsatisfied(Box::new(check)));
     }
 
-    if let Some(max_version) = max_version {
-        let allow_equal = (attributes.unwrap_or_default() & Attributes::MaxVersionInclusive)
-            == Attributes::MaxVersionInclusive as u32;
+    Ok(())
+}
 
-        if !(allow_equal && version <= max_version || version < max_version) {
-            dependencies.insert(dependency);
-            return Err(Error::NotFound);
+/// Checks whether the dependency is registered and within the requested version range.
+///
+/// Unlike [`check_dependencies`], an unsatisfied dependency is not an error but is described by the returned [`DependencyCheck`].
+pub fn check_dependency<S, K>(
+    store: &S,
+    provider_key: K,
+    scope: Scope,
+    range: VersionRange,
+) -> Result<DependencyCheck>
+where
+    S: DependencyStore,
+    K: AsRef<str>,
+{
+    check_dependency_in(store, provider_key, scope.into(), range)
+}
+
+/// Checks whether the dependency is registered in any of the selected scopes and within the requested version range.
+///
+/// Scopes are checked in order of precedence, and the check for the first scope that satisfies the dependency is
+/// returned. If none do, the check for the first scope in which the provider is registered is returned, if any, so
+/// [`DependencyCheck::scope`] reports which scope satisfied the dependency or explains why it is not satisfied.
+pub fn check_dependency_in<S, K>(
+    store: &S,
+    provider_key: K,
+    scopes: ScopeSelector,
+    range: VersionRange,
+) -> Result<DependencyCheck>
+where
+    S: DependencyStore,
+    K: AsRef<str>,
+{
+    let mut checks = Vec::new();
+    for &scope in scopes.scopes() {
+        let key = open_root(store, scope)?;
+        let check = DependencyCheck::check(key.as_ref(), provider_key.as_ref(), scope, range)?;
+        if check.is_satisfied() {
+            return Ok(check);
         }
+        checks.push(check);
     }
 
-    Ok(())
+    let index = checks
+        .iter()
+        .position(|c| c.reason != Some(Unsatisfied::MissingProvider))
+        .unwrap_or_default();
+    Ok(checks.swap_remove(index))
 }
 
 /// Checks that there are no dependents registered for providers that are being uninstalled.
-pub fn check_dependents<K>(
+///
+/// Dependents are returned even if they are no longer installed; see [`find_stale_dependents`].
+pub fn check_dependents<S, K>(
+    store: &S,
     provider_key: K,
     scope: Scope,
-    #[allow(unused_variables)] // Prevent future breaking change; not currently used.
     attributes: Option<Attributes>,
     ignore: Option<&HashSet<String>>,
 ) -> Result<Option<Vec<Dependency>>>
 where
+    S: DependencyStore,
+    K: AsRef<str>,
+{
+    let dependents = check_dependents_in(store, provider_key, scope.into(), attributes, ignore)?;
+    Ok(dependents.map(|dependents| dependents.into_iter().map(|(_, d)| d).collect()))
+}
+
+/// Checks that there are no dependents registered in any of the selected scopes for providers that are being uninstalled.
+///
+/// Dependents are returned with the scope in which they are registered, in order of scope precedence. A dependent
+/// registered in more than one scope is returned for each. See [`check_dependents`].
+pub fn check_dependents_in<S, K>(
+    store: &S,
+    provider_key: K,
+    scopes: ScopeSelector,
+    #[allow(unused_variables)] // Prevent future breaking change; not currently used.
+    attributes: Option<Attributes>,
+    ignore: Option<&HashSet<String>>,
+) -> Result<Option<Vec<(Scope, Dependency)>>>
+where
+    S: DependencyStore,
     K: AsRef<str>,
 {
     // Equivalent to deputil:DepCheckDependents.
+    let mut all: Option<Vec<(Scope, Dependency)>> = None;
+    for &scope in scopes.scopes() {
+        // Failure to open a provider or its Dependents key means no dependents.
+        let Some(key) = open_root(store, scope)? else {
+            continue;
+        };
+
+        let key = match key.open_subkey(provider_key.as_ref()) {
+            Err(Error::NotFound) => continue,
+            err => err,
+        }?;
+
+        let Some(dependents) = read_dependents(&key)? else {
+            continue;
+        };
+
+        // Like deputil, dependents are not checked for a registered provider. Use find_stale_dependents
+        // or prune to find and remove dependents left behind by failed uninstalls.
+        let all = all.get_or_insert_with(Vec::new);
+        for dependent in dependents {
+            if ignore.is_some_and(|ignore| ignore.contains(&dependent.key)) {
+                continue;
+            }
+            all.push((scope, dependent));
+        }
+    }
 
-    // Failure to open a provider or its Dependents key means no dependents.
-    let key = match registry::Key::open::<HKEY, PCWSTR>(scope.into(), ROOT_KEY)
-        .map_err(map_registry_error)
-    {
-        Err(Error::NotFound) => return Ok(None),
-        err => err,
-    }?;
+    Ok(all)
+}
 
-    let provider_key = to_pcwstr(provider_key);
-    let key = match key
-        .open_subkey::<PCWSTR>(provider_key)
-        .map_err(map_registry_error)
-    {
-        Err(Error::NotFound) => return Ok(None),
-        err => err,
-    }?;
+/// Registers a dependent of a provider with an optional version range of the provider it requires.
+///
+/// The provider key is created if it does not already exist.
+pub fn register_dependent<S, K, D>(
+    store: &S,
+    provider_key: K,
+    dependent_key: D,
+    scope: Scope,
+    min_version: Option<Version>,
+    max_version: Option<Version>,
+    attributes: Option<Attributes>,
+) -> Result<()>
+where
+    S: DependencyStore,
+    K: AsRef<str>,
+    D: AsRef<str>,
+{
+    // Equivalent to deputil:DepRegisterDependent.
+    store.exclusive(scope, || {
+        let key = store
+            .create(scope)?
+            .create_subkey(provider_key.as_ref())?
+            .create_subkey(DEPENDENTS_KEY)?
+            .create_subkey(dependent_key.as_ref())?;
+
+        if let Some(min_version) = min_version {
+            key.set_value(Some("MinVersion"), Data::String(min_version.to_string()))?;
+        }
+        if let Some(max_version) = max_version {
+            key.set_value(Some("MaxVersion"), Data::String(max_version.to_string()))?;
+        }
+        match attributes {
+            Some(attributes) if !attributes.is_empty() => {
+                key.set_value(Some("Attributes"), Data::DWord(attributes.bits()))?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    })
+}
+
+/// Unregisters a dependent of a provider.
+///
+/// The `Dependents` key and then the provider key are removed if they are left empty.
+pub fn unregister_dependent<S, K, D>(
+    store: &S,
+    provider_key: K,
+    dependent_key: D,
+    scope: Scope,
+) -> Result<()>
+where
+    S: DependencyStore,
+    K: AsRef<str>,
+    D: AsRef<str>,
+{
+    // Equivalent to deputil:DepUnregisterDependent.
+    store.exclusive(scope, || {
+        // Check the root key exists first so that a missing root key is not created only to delete from it.
+        if open_root(store, scope)?.is_none() {
+            return Err(Error::NotFound);
+        }
+
+        let key = store.create(scope)?;
+        let provider = key.open_subkey(provider_key.as_ref())?;
+        let dependents = provider.open_subkey(DEPENDENTS_KEY)?;
+        dependents.delete_subkey(dependent_key.as_ref())?;
+
+        if is_empty(&dependents)? {
+            provider.delete_subkey(DEPENDENTS_KEY)?;
+            if is_empty(&provider)? {
+                key.delete_subkey(provider_key.as_ref())?;
+            }
+        }
+
+        Ok(())
+    })
+}
 
-    let key = match key
-        .open_subkey::<PCWSTR>(DEPENDENTS_KEY)
-        .map_err(map_registry_error)
-    {
+/// Unregisters a provider along with any dependents registered for it.
+///
+/// Returns [`Error::HasDependents`] if any dependents are still registered for the provider unless `force` is true.
+pub fn unregister_provider<S, K>(
+    store: &S,
+    provider_key: K,
+    scope: Scope,
+    force: bool,
+) -> Result<()>
+where
+    S: DependencyStore,
+    K: AsRef<str>,
+{
+    // Equivalent to deputil:DepUnregisterDependency.
+    store.exclusive(scope, || {
+        if !force {
+            if let Some(dependents) =
+                check_dependents(store, provider_key.as_ref(), scope, None, None)?
+            {
+                if !dependents.is_empty() {
+                    return Err(Error::HasDependents(dependents));
+                }
+            }
+        }
+
+        // Check the root key exists first so that a missing root key is not created only to delete from it.
+        if open_root(store, scope)?.is_none() {
+            return Err(Error::NotFound);
+        }
+
+        store.create(scope)?.delete_subkey(provider_key.as_ref())
+    })
+}
+
+/// Opens the root key for a scope, or `None` if it does not exist.
+pub(crate) fn open_root<S: DependencyStore>(store: &S, scope: Scope) -> Result<Option<S::Key>> {
+    match store.open(scope) {
+        Err(Error::NotFound) => Ok(None),
+        key => key.map(Some),
+    }
+}
+
+/// Reads the dependents registered for a provider key, or `None` if it has no `Dependents` key.
+pub(crate) fn read_dependents(key: &impl StoreKey) -> Result<Option<Vec<Dependency>>> {
+    let key = match key.open_subkey(DEPENDENTS_KEY) {
         Err(Error::NotFound) => return Ok(None),
         err => err,
     }?;
 
     Ok(Some(
         key.keys()?
-            .filter_map(|k| {
-                if let Some(ignore) = ignore {
-                    if ignore.contains(&k.name) {
-                        return None;
-                    }
-                }
-
-                // BUGBUG: Should we check that the provider actually exists in case it didn't clean up during uninstall or was that meant for permanent packages?
-                Some(Dependency::new(&k.name))
+            .into_iter()
+            .map(|k| match key.open_subkey(&k) {
+                Ok(dependent) => Dependency::from(k, &dependent),
+                Err(_) => Dependency::new(k),
             })
             .collect(),
     ))
 }
 
-impl BitAnd for Attributes {
-    type Output = u32;
-    // cspell:ignore bitand
-    fn bitand(self, rhs: Self) -> Self::Output {
-        (self as u32) & (rhs as u32)
-    }
-}
-
-impl BitOr for Attributes {
-    type Output = u32;
-    fn bitor(self, rhs: Self) -> Self::Output {
-        (self as u32) | (rhs as u32)
-    }
+fn is_empty(key: &impl StoreKey) -> Result<bool> {
+    Ok(key.keys()?.is_empty() && key.values()?.is_empty())
 }
 
 impl Display for Scope {
@@ -207,6 +458,28 @@ impl Display for Scope {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Scope {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Scope {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        value
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid scope {:?}", value)))
+    }
+}
+
 impl FromStr for Scope {
     type Err = Error;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -218,32 +491,482 @@ impl FromStr for Scope {
     }
 }
 
-impl From<Scope> for windows::Win32::System::Registry::HKEY {
-    fn from(value: Scope) -> Self {
-        match value {
-            Scope::User => registry::HKEY_CURRENT_USER,
-            Scope::Machine => registry::HKEY_LOCAL_MACHINE,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(key: &str, version: [u16; 4]) -> Provider {
+        Provider {
+            key: key.to_string(),
+            name: format!("Test {key}"),
+            version: Version::from(version),
+            ..Default::default()
         }
     }
-}
 
-fn to_pcwstr(value: impl AsRef<str>) -> PCWSTR {
-    let value: Vec<u16> = value
-        .as_ref()
-        .encode_utf16()
-        .chain(std::iter::once(0))
-        .collect();
-    PCWSTR::from_raw(value.as_ptr())
-}
+    fn reason(err: Error) -> Option<Unsatisfied> {
+        match err {
+            Error::Unsatisfied(check) => check.reason,
+            _ => None,
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn add_dependent(store: &MemoryStore, provider_key: &str, dependent_key: &str, scope: Scope) {
+        register_dependent(store, provider_key, dependent_key, scope, None, None, None).unwrap();
+    }
+
+    #[test]
+    fn get_provider_registered() {
+        let store = MemoryStore::new();
+        let expected = Provider {
+            id: Some("{00000000-0000-0000-0000-000000000000}".to_string()),
+            ..provider("test", [1, 2, 3, 4])
+        };
+        expected.register(&store, Scope::Machine).unwrap();
+
+        let actual = get_provider(&store, "TEST", Scope::Machine).unwrap();
+        assert_eq!(actual.key, "TEST");
+        assert_eq!(actual.name, "Test test");
+        assert_eq!(actual.version, Version::from([1, 2, 3, 4]));
+        assert_eq!(actual.id, expected.id);
+    }
+
+    #[test]
+    fn get_provider_wrong_scope() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+
+        assert_eq!(
+            get_provider(&store, "test", Scope::User).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn check_dependencies_satisfied() {
+        let store = MemoryStore::new();
+        provider("test", [1, 2, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+
+        let mut dependencies = HashSet::new();
+        check_dependencies(
+            &store,
+            "test",
+            Scope::Machine,
+            Some(Version::from([1, 0, 0, 0])),
+            Some(Version::from([2, 0, 0, 0])),
+            None,
+            &mut dependencies,
+        )
+        .unwrap();
+        assert!(dependencies.is_empty());
+    }
+
+    #[test]
+    fn check_dependencies_missing() {
+        let store = MemoryStore::new();
+        store.create(Scope::Machine).unwrap();
+
+        let mut dependencies = HashSet::new();
+        assert_eq!(
+            reason(
+                check_dependencies(
+                    &store,
+                    "test",
+                    Scope::Machine,
+                    None,
+                    None,
+                    None,
+                    &mut dependencies,
+                )
+                .unwrap_err()
+            ),
+            Some(Unsatisfied::MissingProvider)
+        );
+        assert!(dependencies.contains(&Dependency::new("test")));
+    }
+
+    #[test]
+    fn check_dependencies_missing_version() {
+        let store = MemoryStore::new();
+        store
+            .create(Scope::Machine)
+            .and_then(|k| k.create_subkey("test"))
+            .unwrap();
+
+        let mut dependencies = HashSet::new();
+        assert_eq!(
+            reason(
+                check_dependencies(
+                    &store,
+                    "test",
+                    Scope::Machine,
+                    None,
+                    None,
+                    None,
+                    &mut dependencies,
+                )
+                .unwrap_err()
+            ),
+            Some(Unsatisfied::MissingVersion)
+        );
+        assert!(dependencies.contains(&Dependency::new("test")));
+    }
+
+    #[test]
+    fn check_dependencies_inclusive() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+
+        let mut dependencies = HashSet::new();
+        assert_eq!(
+            reason(
+                check_dependencies(
+                    &store,
+                    "test",
+                    Scope::Machine,
+                    Some(Version::from([1, 0, 0, 0])),
+                    None,
+                    None,
+                    &mut dependencies,
+                )
+                .unwrap_err()
+            ),
+            Some(Unsatisfied::BelowMinimum)
+        );
+
+        dependencies.clear();
+        check_dependencies(
+            &store,
+            "test",
+            Scope::Machine,
+            Some(Version::from([1, 0, 0, 0])),
+            None,
+            Some(Attributes::MIN_VERSION_INCLUSIVE),
+            &mut dependencies,
+        )
+        .unwrap();
+        assert!(dependencies.is_empty());
+    }
+
+    #[test]
+    fn scope_selector_precedence() {
+        assert_eq!(ScopeSelector::from(Scope::User).scopes(), &[Scope::User]);
+        assert_eq!(ScopeSelector::default().scopes(), &[Scope::Machine]);
+        assert_eq!(
+            ScopeSelector::Both(Scope::User).scopes(),
+            &[Scope::User, Scope::Machine]
+        );
+        assert_eq!(
+            ScopeSelector::Both(Scope::Machine).scopes(),
+            &[Scope::Machine, Scope::User]
+        );
+    }
+
+    #[test]
+    fn check_dependency_both_scopes() {
+        let store = MemoryStore::new();
+        provider("runtime", [1, 0, 0, 0])
+            .register(&store, Scope::User)
+            .unwrap();
+        provider("runtime", [2, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        provider("tools", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+
+        let range = VersionRange::new(
+            Some(Version::from([2, 0, 0, 0])),
+            None,
+            Attributes::MIN_VERSION_INCLUSIVE,
+        );
+        let check = check_dependency_in(&store, "runtime", ScopeSelector::Both(Scope::User), range)
+            .unwrap();
+        assert!(check.is_satisfied());
+        assert_eq!(check.scope, Scope::Machine);
+        assert_eq!(check.installed, Some(Version::from([2, 0, 0, 0])));
+
+        let check = check_dependency(&store, "runtime", Scope::User, range).unwrap();
+        assert_eq!(check.reason, Some(Unsatisfied::BelowMinimum));
+
+        // The first scope in which the provider is registered explains why it is not satisfied.
+        let check =
+            check_dependency_in(&store, "tools", ScopeSelector::Both(Scope::User), range).unwrap();
+        assert_eq!(check.scope, Scope::Machine);
+        assert_eq!(check.reason, Some(Unsatisfied::BelowMinimum));
+
+        let check = check_dependency_in(&store, "missing", ScopeSelector::Both(Scope::User), range)
+            .unwrap();
+        assert_eq!(check.scope, Scope::User);
+        assert_eq!(check.reason, Some(Unsatisfied::MissingProvider));
+
+        let mut dependencies = HashSet::new();
+        check_dependencies_in(
+            &store,
+            "tools",
+            ScopeSelector::Both(Scope::Machine),
+            None,
+            None,
+            None,
+            &mut dependencies,
+        )
+        .unwrap();
+        assert!(dependencies.is_empty());
+
+        assert!(check_dependencies_in(
+            &store,
+            "tools",
+            ScopeSelector::Both(Scope::Machine),
+            Some(Version::from([2, 0, 0, 0])),
+            None,
+            None,
+            &mut dependencies,
+        )
+        .is_err());
+        assert_eq!(dependencies.len(), 1);
+    }
+
+    #[test]
+    fn check_dependents_both_scopes() {
+        let store = MemoryStore::new();
+        add_dependent(&store, "test", "foo", Scope::User);
+        add_dependent(&store, "test", "bar", Scope::Machine);
+        add_dependent(&store, "test", "FOO", Scope::Machine);
+
+        assert_eq!(
+            check_dependents_in(
+                &store,
+                "test",
+                ScopeSelector::Both(Scope::Machine),
+                None,
+                None
+            )
+            .unwrap(),
+            Some(vec![
+                (Scope::Machine, Dependency::new("bar")),
+                (Scope::Machine, Dependency::new("FOO")),
+                (Scope::User, Dependency::new("foo")),
+            ])
+        );
+        assert_eq!(
+            check_dependents_in(&store, "test", ScopeSelector::Both(Scope::User), None, None)
+                .unwrap(),
+            Some(vec![
+                (Scope::User, Dependency::new("foo")),
+                (Scope::Machine, Dependency::new("bar")),
+                (Scope::Machine, Dependency::new("FOO")),
+            ])
+        );
+        assert_eq!(
+            check_dependents_in(
+                &store,
+                "other",
+                ScopeSelector::Both(Scope::User),
+                None,
+                None
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn check_dependents_none() {
+        let store = MemoryStore::new();
+        assert_eq!(
+            check_dependents(&store, "test", Scope::Machine, None, None).unwrap(),
+            None
+        );
+
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        assert_eq!(
+            check_dependents(&store, "test", Scope::Machine, None, None).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn check_dependents_ignored() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        add_dependent(&store, "test", "foo", Scope::Machine);
+        add_dependent(&store, "test", "bar", Scope::Machine);
+
+        let ignore = HashSet::from(["foo".to_string()]);
+        assert_eq!(
+            check_dependents(&store, "test", Scope::Machine, None, Some(&ignore)).unwrap(),
+            Some(vec![Dependency::new("bar")])
+        );
+    }
+
+    #[test]
+    fn unregister_provider_no_dependents() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+
+        unregister_provider(&store, "test", Scope::Machine, false).unwrap();
+        assert_eq!(
+            get_provider(&store, "test", Scope::Machine).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn unregister_provider_missing() {
+        let store = MemoryStore::new();
+        assert_eq!(
+            unregister_provider(&store, "test", Scope::Machine, false).unwrap_err(),
+            Error::NotFound
+        );
+        assert_eq!(store.open(Scope::Machine).unwrap_err(), Error::NotFound);
+    }
+
+    #[test]
+    fn unregister_provider_has_dependents() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        add_dependent(&store, "test", "foo", Scope::Machine);
+
+        assert_eq!(
+            unregister_provider(&store, "test", Scope::Machine, false).unwrap_err(),
+            Error::HasDependents(vec![Dependency::new("foo")])
+        );
+        assert!(get_provider(&store, "test", Scope::Machine).is_ok());
+
+        unregister_provider(&store, "test", Scope::Machine, true).unwrap();
+        assert_eq!(
+            get_provider(&store, "test", Scope::Machine).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn register_dependent_range() {
+        let store = MemoryStore::new();
+        register_dependent(
+            &store,
+            "test",
+            "foo",
+            Scope::User,
+            Some(Version::from([1, 0, 0, 0])),
+            Some(Version::from([2, 0, 0, 0])),
+            Some(Attributes::MIN_VERSION_INCLUSIVE),
+        )
+        .unwrap();
+
+        let dependents = check_dependents(&store, "test", Scope::User, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].key, "foo");
+        assert_eq!(dependents[0].min_version, Some(Version::from([1, 0, 0, 0])));
+        assert_eq!(dependents[0].max_version, Some(Version::from([2, 0, 0, 0])));
+        assert_eq!(
+            dependents[0].attributes,
+            Some(Attributes::MIN_VERSION_INCLUSIVE)
+        );
+    }
+
+    #[test]
+    fn unregister_dependent_removes_empty_keys() {
+        let store = MemoryStore::new();
+        add_dependent(&store, "test", "foo", Scope::Machine);
+        add_dependent(&store, "test", "bar", Scope::Machine);
+
+        unregister_dependent(&store, "test", "foo", Scope::Machine).unwrap();
+        assert_eq!(
+            check_dependents(&store, "test", Scope::Machine, None, None).unwrap(),
+            Some(vec![Dependency::new("bar")])
+        );
+
+        unregister_dependent(&store, "test", "bar", Scope::Machine).unwrap();
+        let key = store.open(Scope::Machine).unwrap();
+        assert!(key.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unregister_dependent_keeps_provider() {
+        let store = MemoryStore::new();
+        provider("test", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        add_dependent(&store, "test", "foo", Scope::Machine);
+
+        unregister_dependent(&store, "test", "foo", Scope::Machine).unwrap();
+        assert_eq!(
+            check_dependents(&store, "test", Scope::Machine, None, None).unwrap(),
+            None
+        );
+        assert!(get_provider(&store, "test", Scope::Machine).is_ok());
+    }
+
+    #[test]
+    fn unregister_dependent_missing() {
+        let store = MemoryStore::new();
+        add_dependent(&store, "test", "foo", Scope::Machine);
+        assert_eq!(
+            unregister_dependent(&store, "test", "bar", Scope::Machine).unwrap_err(),
+            Error::NotFound
+        );
+        assert_eq!(
+            unregister_dependent(&store, "other", "foo", Scope::Machine).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn providers_empty() {
+        let store = MemoryStore::new();
+        assert_eq!(providers(&store, Scope::Machine).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn providers_malformed() {
+        let store = MemoryStore::new();
+        provider("foo", [1, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        provider("bar", [2, 0, 0, 0])
+            .register(&store, Scope::Machine)
+            .unwrap();
+        store
+            .open(Scope::Machine)
+            .and_then(|k| k.create_subkey("baz"))
+            .and_then(|k| k.set_value(Some("Version"), Data::String("invalid".to_string())))
+            .unwrap();
+
+        let actual: Vec<_> = providers(&store, Scope::Machine).unwrap().collect();
+        assert_eq!(actual.len(), 3);
+        assert_eq!(actual[0].as_ref().unwrap().key, "bar");
+        assert_eq!(
+            actual[1].as_ref().unwrap_err(),
+            &Error::InvalidProvider("baz".to_string(), Box::new(Error::Format))
+        );
+        assert_eq!(actual[2].as_ref().unwrap().key, "foo");
+    }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_to_pcwstr() {
-        let value = to_pcwstr("test");
-        let (_, value, _) = unsafe { value.as_wide().align_to::<u8>() };
-        assert_eq!(value, b"t\0e\0s\0t\0");
+    fn scope_serde() {
+        assert_eq!(serde_json::to_string(&Scope::User).unwrap(), r#""user""#);
+        assert_eq!(
+            serde_json::from_str::<Scope>(r#""Machine""#).unwrap(),
+            Scope::Machine
+        );
+        assert!(serde_json::from_str::<Scope>(r#""system""#).is_err());
     }
 }
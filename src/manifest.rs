@@ -0,0 +1,203 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::{Attributes, Error, Requirement, Requirements, Result, Scope, Version, VersionRange};
+
+// A manifest of requirements e.g., in TOML:
+//
+// [[requirements]]
+// key = "Microsoft.VCRedist"
+// min_version = "14.0"
+// min_inclusive = true
+// scope = "machine"
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default)]
+    requirements: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    key: String,
+
+    #[serde(default)]
+    min_version: Option<Version>,
+
+    #[serde(default)]
+    max_version: Option<Version>,
+
+    #[serde(default)]
+    min_inclusive: bool,
+
+    #[serde(default)]
+    max_inclusive: bool,
+
+    #[serde(default)]
+    scope: Scope,
+}
+
+impl From<Entry> for Requirement {
+    fn from(value: Entry) -> Self {
+        let mut attributes = Attributes::NONE;
+        if value.min_inclusive {
+            attributes |= Attributes::MIN_VERSION_INCLUSIVE;
+        }
+        if value.max_inclusive {
+            attributes |= Attributes::MAX_VERSION_INCLUSIVE;
+        }
+
+        Requirement::new(
+            value.key,
+            VersionRange::new(value.min_version, value.max_version, attributes),
+            value.scope,
+        )
+    }
+}
+
+impl Requirements {
+    /// Parses requirements from a TOML manifest containing a `[[requirements]]` array of tables.
+    ///
+    /// Each requirement has a `key` and optional `min_version`, `max_version`, `min_inclusive`, `max_inclusive`, and `scope`.
+    /// Returns [`Error::Parse`] with the line and column of any invalid content.
+    pub fn from_toml(s: &str) -> Result<Self> {
+        let manifest: Manifest = toml::from_str(s).map_err(|err| Error::toml(s, err))?;
+
+        Ok(manifest.requirements.into_iter().map(Into::into).collect())
+    }
+
+    /// Parses requirements from a JSON manifest containing a `requirements` array of objects.
+    ///
+    /// Each requirement has a `key` and optional `min_version`, `max_version`, `min_inclusive`, `max_inclusive`, and `scope`.
+    /// Returns [`Error::Parse`] with the line and column of any invalid content.
+    pub fn from_json(s: &str) -> Result<Self> {
+        let manifest: Manifest = serde_json::from_str(s).map_err(Error::json)?;
+
+        Ok(manifest.requirements.into_iter().map(Into::into).collect())
+    }
+
+    /// Reads requirements from a TOML or JSON manifest file based on its `.toml` or `.json` extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase);
+
+        match extension.as_deref() {
+            Some("toml") => Requirements::from_toml(&std::fs::read_to_string(path)?),
+            Some("json") => Requirements::from_json(&std::fs::read_to_string(path)?),
+            _ => Err(Error::NotSupported),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_toml() {
+        let requirements = Requirements::from_toml(
+            r#"
+[[requirements]]
+key = "runtime"
+min_version = "2.0"
+max_version = "3.0"
+min_inclusive = true
+
+[[requirements]]
+key = "tools"
+scope = "user"
+"#,
+        )
+        .unwrap();
+
+        let requirements: Vec<_> = requirements.into_iter().collect();
+        assert_eq!(
+            requirements,
+            vec![
+                Requirement::new("runtime", "[2.0,3.0)".parse().unwrap(), Scope::Machine),
+                Requirement::new("tools", Default::default(), Scope::User),
+            ]
+        );
+    }
+
+    #[test]
+    fn from_toml_invalid_version() {
+        let err = Requirements::from_toml(
+            r#"[[requirements]]
+key = "runtime"
+min_version = "2.x"
+"#,
+        )
+        .unwrap_err();
+
+        let Error::Parse {
+            line,
+            column,
+            message,
+        } = err
+        else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!((line, column), (3, 15));
+        assert_eq!(message, "invalid version \"2.x\"");
+    }
+
+    #[test]
+    fn from_toml_unknown_field() {
+        assert!(matches!(
+            Requirements::from_toml("[[requirements]]\nkey = \"a\"\nminimum = \"1.0\"\n"),
+            Err(Error::Parse { line: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn from_json() {
+        let requirements = Requirements::from_json(
+            r#"{
+  "requirements": [
+    { "key": "runtime", "max_version": "3.0", "max_inclusive": true, "scope": "Machine" }
+  ]
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            requirements.iter().next().unwrap(),
+            &Requirement::new("runtime", "(,3.0]".parse().unwrap(), Scope::Machine)
+        );
+    }
+
+    #[test]
+    fn from_json_invalid_version() {
+        let err = Requirements::from_json(
+            r#"{
+  "requirements": [
+    { "key": "runtime", "min_version": "1.2.3.4.5" }
+  ]
+}"#,
+        )
+        .unwrap_err();
+
+        let Error::Parse { line, message, .. } = err else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(line, 3);
+        assert_eq!(message, "invalid version \"1.2.3.4.5\"");
+    }
+
+    #[test]
+    fn from_path_not_supported() {
+        assert_eq!(
+            Requirements::from_path("requirements.yaml").unwrap_err(),
+            Error::NotSupported
+        );
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::{Error, Result, Scope};
+
+/// An in-memory [`DependencyStore`] with separate user and machine scopes.
+///
+/// Key and value names are case-insensitive like the Windows registry. Clones share the same data,
+/// so a store can be seeded in one place and inspected from another.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+    scopes: Arc<RwLock<Scopes>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store in which no scope has a root key.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Opens or creates the key under which products are registered with Add/Remove Programs for the given scope.
+    pub fn create_uninstall(&self, scope: Scope) -> Result<MemoryKey> {
+        self.create_tree(scope, Tree::Uninstall)
+    }
+
+    fn open_tree(&self, scope: Scope, tree: Tree) -> Result<MemoryKey> {
+        let scopes = self.scopes.read().unwrap_or_else(PoisonError::into_inner);
+        let root = scopes.root(scope, tree).as_ref().ok_or(Error::NotFound)?;
+
+        Ok(MemoryKey {
+            scopes: self.scopes.clone(),
+            scope,
+            tree,
+            path: Vec::new(),
+            name: root.name.clone(),
+        })
+    }
+
+    fn create_tree(&self, scope: Scope, tree: Tree) -> Result<MemoryKey> {
+        let mut scopes = self.scopes.write().unwrap_or_else(PoisonError::into_inner);
+        let root = scopes
+            .root_mut(scope, tree)
+            .get_or_insert_with(|| Node::new(tree.name()));
+
+        Ok(MemoryKey {
+            scopes: self.scopes.clone(),
+            scope,
+            tree,
+            path: Vec::new(),
+            name: root.name.clone(),
+        })
+    }
+}
+
+impl DependencyStore for MemoryStore {
+    type Key = MemoryKey;
+
+    fn open(&self, scope: Scope) -> Result<MemoryKey> {
+        self.open_tree(scope, Tree::Dependencies)
+    }
+
+    fn create(&self, scope: Scope) -> Result<MemoryKey> {
+        self.create_tree(scope, Tree::Dependencies)
+    }
+
+    fn open_uninstall(&self, scope: Scope) -> Result<Vec<MemoryKey>> {
+        match self.open_tree(scope, Tree::Uninstall) {
+            Err(Error::NotFound) => Ok(Vec::new()),
+            key => key.map(|key| vec![key]),
+        }
+    }
+}
+
+/// A key within a [`MemoryStore`].
+///
+/// The key refers to a path within the store, so operations on a key that was since deleted return [`Error::NotFound`].
+#[derive(Clone, Debug)]
+pub struct MemoryKey {
+    scopes: Arc<RwLock<Scopes>>,
+    scope: Scope,
+    tree: Tree,
+    path: Vec<String>,
+    name: String,
+}
+
+impl MemoryKey {
+    fn subkey(&self, name: &str) -> Self {
+        let mut path = self.path.clone();
+        path.push(name.to_uppercase());
+
+        MemoryKey {
+            scopes: self.scopes.clone(),
+            scope: self.scope,
+            tree: self.tree,
+            path,
+            name: name.to_string(),
+        }
+    }
+
+    fn with_node<T>(&self, f: impl FnOnce(&Node) -> Result<T>) -> Result<T> {
+        let scopes = self.scopes.read().unwrap_or_else(PoisonError::into_inner);
+        let node = scopes
+            .root(self.scope, self.tree)
+            .as_ref()
+            .and_then(|root| root.find(&self.path))
+            .ok_or(Error::NotFound)?;
+
+        f(node)
+    }
+
+    fn with_node_mut<T>(&self, f: impl FnOnce(&mut Node) -> Result<T>) -> Result<T> {
+        let mut scopes = self.scopes.write().unwrap_or_else(PoisonError::into_inner);
+        let node = scopes
+            .root_mut(self.scope, self.tree)
+            .as_mut()
+            .and_then(|root| root.find_mut(&self.path))
+            .ok_or(Error::NotFound)?;
+
+        f(node)
+    }
+}
+
+impl StoreKey for MemoryKey {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open_subkey(&self, name: &str) -> Result<Self> {
+        self.with_node(|node| {
+            let child = node.keys.get(&name.to_uppercase()).ok_or(Error::NotFound)?;
+            Ok(self.subkey(&child.name))
+        })
+    }
+
+    fn create_subkey(&self, name: &str) -> Result<Self> {
+        self.with_node_mut(|node| {
+            let child = node
+                .keys
+                .entry(name.to_uppercase())
+                .or_insert_with(|| Node::new(name));
+            Ok(self.subkey(&child.name))
+        })
+    }
+
+    fn delete_subkey(&self, name: &str) -> Result<()> {
+        self.with_node_mut(|node| {
+            node.keys
+                .remove(&name.to_uppercase())
+                .map(|_| ())
+                .ok_or(Error::NotFound)
+        })
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        self.with_node(|node| Ok(node.keys.values().map(|k| k.name.clone()).collect()))
+    }
+
+    fn values(&self) -> Result<Vec<(Option<String>, Data)>> {
+        self.with_node(|node| {
+            Ok(node
+                .values
+                .values()
+                .map(|(name, data)| {
+                    let name = (!name.is_empty()).then(|| name.clone());
+                    (name, data.clone())
+                })
+                .collect())
+        })
+    }
+
+    fn value(&self, name: Option<&str>) -> Result<Data> {
+        self.with_node(|node| {
+            node.values
+                .get(&value_key(name))
+                .map(|(_, data)| data.clone())
+                .ok_or(Error::NotFound)
+        })
+    }
+
+    fn set_value(&self, name: Option<&str>, data: Data) -> Result<()> {
+        self.with_node_mut(|node| {
+            node.values.insert(
+                value_key(name),
+                (name.unwrap_or_default().to_string(), data),
+            );
+            Ok(())
+        })
+    }
+
+    fn delete_value(&self, name: Option<&str>) -> Result<()> {
+        self.with_node_mut(|node| {
+            node.values
+                .remove(&value_key(name))
+                .map(|_| ())
+                .ok_or(Error::NotFound)
+        })
+    }
+}
+
+// The trees of keys within each scope.
+#[derive(Clone, Copy, Debug)]
+enum Tree {
+    Dependencies,
+    Uninstall,
+}
+
+impl Tree {
+    fn name(self) -> &'static str {
+        match self {
+            Tree::Dependencies => "Dependencies",
+            Tree::Uninstall => "Uninstall",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Scopes {
+    user: Option<Node>,
+    machine: Option<Node>,
+    user_uninstall: Option<Node>,
+    machine_uninstall: Option<Node>,
+}
+
+impl Scopes {
+    fn root(&self, scope: Scope, tree: Tree) -> &Option<Node> {
+        match (scope, tree) {
+            (Scope::User, Tree::Dependencies) => &self.user,
+            (Scope::Machine, Tree::Dependencies) => &self.machine,
+            (Scope::User, Tree::Uninstall) => &self.user_uninstall,
+            (Scope::Machine, Tree::Uninstall) => &self.machine_uninstall,
+        }
+    }
+
+    fn root_mut(&mut self, scope: Scope, tree: Tree) -> &mut Option<Node> {
+        match (scope, tree) {
+            (Scope::User, Tree::Dependencies) => &mut self.user,
+            (Scope::Machine, Tree::Dependencies) => &mut self.machine,
+            (Scope::User, Tree::Uninstall) => &mut self.user_uninstall,
+            (Scope::Machine, Tree::Uninstall) => &mut self.machine_uninstall,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+
+    // Keyed by upper-case names for case-insensitive lookups while preserving the original names.
+    values: BTreeMap<String, (String, Data)>,
+    keys: BTreeMap<String, Node>,
+}
+
+impl Node {
+    fn new(name: &str) -> Self {
+        Node {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn find(&self, path: &[String]) -> Option<&Node> {
+        path.iter().try_fold(self, |node, name| node.keys.get(name))
+    }
+
+    fn find_mut(&mut self, path: &[String]) -> Option<&mut Node> {
+        path.iter()
+            .try_fold(self, |node, name| node.keys.get_mut(name))
+    }
+}
+
+fn value_key(name: Option<&str>) -> String {
+    name.unwrap_or_default().to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_missing_root() {
+        let store = MemoryStore::new();
+        assert_eq!(store.open(Scope::Machine).unwrap_err(), Error::NotFound);
+    }
+
+    #[test]
+    fn create_root() {
+        let store = MemoryStore::new();
+        store.create(Scope::Machine).unwrap();
+        assert!(store.open(Scope::Machine).is_ok());
+        assert_eq!(store.open(Scope::User).unwrap_err(), Error::NotFound);
+    }
+
+    #[test]
+    fn create_uninstall() {
+        let store = MemoryStore::new();
+        store
+            .create(Scope::Machine)
+            .unwrap()
+            .create_subkey("a")
+            .unwrap();
+        assert!(store.open_uninstall(Scope::Machine).unwrap().is_empty());
+
+        let uninstall = store.create_uninstall(Scope::Machine).unwrap();
+        uninstall.create_subkey("b").unwrap();
+        assert_eq!(uninstall.name(), "Uninstall");
+        assert_eq!(
+            store.open_uninstall(Scope::Machine).unwrap()[0]
+                .keys()
+                .unwrap(),
+            vec!["b"]
+        );
+        assert_eq!(
+            store.open(Scope::Machine).unwrap().keys().unwrap(),
+            vec!["a"]
+        );
+    }
+
+    #[test]
+    fn subkeys_case_insensitive() {
+        let store = MemoryStore::new();
+        let root = store.create(Scope::User).unwrap();
+        root.create_subkey("Foo").unwrap();
+        root.create_subkey("bar").unwrap();
+
+        let key = root.open_subkey("FOO").unwrap();
+        assert_eq!(key.name(), "Foo");
+        assert_eq!(root.keys().unwrap(), vec!["bar", "Foo"]);
+
+        root.delete_subkey("foo").unwrap();
+        assert_eq!(root.keys().unwrap(), vec!["bar"]);
+        assert_eq!(root.delete_subkey("foo").unwrap_err(), Error::NotFound);
+    }
+
+    #[test]
+    fn values_case_insensitive() {
+        let store = MemoryStore::new();
+        let root = store.create(Scope::User).unwrap();
+        root.set_value(Some("Version"), Data::String("1.0".to_string()))
+            .unwrap();
+        root.set_value(None, Data::DWord(1)).unwrap();
+
+        assert_eq!(
+            root.value(Some("VERSION")).unwrap(),
+            Data::String("1.0".to_string())
+        );
+        assert_eq!(root.value(None).unwrap(), Data::DWord(1));
+        assert_eq!(
+            root.values().unwrap(),
+            vec![
+                (None, Data::DWord(1)),
+                (Some("Version".to_string()), Data::String("1.0".to_string())),
+            ]
+        );
+
+        root.delete_value(Some("version")).unwrap();
+        assert_eq!(root.value(Some("Version")).unwrap_err(), Error::NotFound);
+        assert_eq!(
+            root.delete_value(Some("Version")).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn deleted_key_not_found() {
+        let store = MemoryStore::new();
+        let root = store.create(Scope::Machine).unwrap();
+        let key = root.create_subkey("test").unwrap();
+        root.delete_subkey("test").unwrap();
+
+        assert_eq!(key.keys().unwrap_err(), Error::NotFound);
+        assert_eq!(
+            key.set_value(None, Data::DWord(1)).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn scopes_separate() {
+        let store = MemoryStore::new();
+        store
+            .create(Scope::User)
+            .unwrap()
+            .create_subkey("test")
+            .unwrap();
+        let root = store.create(Scope::Machine).unwrap();
+        assert!(root.keys().unwrap().is_empty());
+    }
+
+    #[test]
+    fn clones_share_data() {
+        let store = MemoryStore::new();
+        let clone = store.clone();
+        store
+            .create(Scope::Machine)
+            .unwrap()
+            .create_subkey("test")
+            .unwrap();
+        assert!(clone
+            .open(Scope::Machine)
+            .unwrap()
+            .open_subkey("test")
+            .is_ok());
+    }
+}
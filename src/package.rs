@@ -0,0 +1,322 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use msi::{Package, Select};
+
+use crate::{Attributes, Dependency, Error, Provider, Result, Version};
+
+// Table names written by the WiX v3 and v4 Dependency extensions, in that order.
+const PROVIDER_TABLES: [&str; 2] = ["WixDependencyProvider", "Wix4DependencyProvider"];
+const DEPENDENCY_TABLES: [&str; 2] = ["WixDependency", "Wix4Dependency"];
+const REF_TABLES: [&str; 2] = ["WixDependencyRef", "Wix4DependencyRef"];
+
+/// A provider registered by a package along with the dependencies it requires.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackageProvider {
+    /// The provider registered when the package is installed.
+    pub provider: Provider,
+
+    /// Dependencies that must be registered before the package is installed.
+    pub requires: Vec<Dependency>,
+}
+
+/// Dependency data from the `WixDependencyProvider`, `WixDependency`, and `WixDependencyRef` tables of an MSI package.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MsiPackage {
+    /// The `ProductCode` property, which is also the [`Provider::id`] of each provider.
+    pub product_code: Option<String>,
+
+    /// Providers in table order.
+    pub providers: Vec<PackageProvider>,
+
+    /// All dependencies in table order, whether referenced by a provider or not.
+    pub dependencies: Vec<Dependency>,
+}
+
+impl MsiPackage {
+    /// Reads dependency data from an MSI package file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        MsiPackage::from_reader(File::open(path)?)
+    }
+
+    /// Reads dependency data from an MSI package.
+    ///
+    /// Tables written by either the WiX v3 or v4 Dependency extension are read. A package without these tables has
+    /// no providers or dependencies. Providers without a version or display name default to the `ProductVersion` and
+    /// `ProductName` properties.
+    pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut package = Package::open(reader)?;
+        let properties: HashMap<String, String> = select(&mut package, &["Property"])?
+            .into_iter()
+            .filter_map(|row| Some((string(&row, "Property")?, string(&row, "Value")?)))
+            .collect();
+        let product_code = properties.get("ProductCode").cloned();
+
+        let mut dependencies = Vec::new();
+        let mut ids = HashMap::new();
+        for row in select(&mut package, &DEPENDENCY_TABLES)? {
+            let key = string(&row, "ProviderKey").ok_or(Error::Format)?;
+            let version = |name| string(&row, name).map(|v| version(&v)).transpose();
+            let dependency = Dependency {
+                min_version: version("MinVersion")?,
+                max_version: version("MaxVersion")?,
+                attributes: attributes(&row),
+                key,
+            };
+
+            if let Some(id) = row[0].as_str() {
+                ids.insert(id.to_string(), dependencies.len());
+            }
+            dependencies.push(dependency);
+        }
+
+        let mut refs: HashMap<String, Vec<usize>> = HashMap::new();
+        for row in select(&mut package, &REF_TABLES)? {
+            let (Some(provider), Some(dependency)) = (row[0].as_str(), row[1].as_str()) else {
+                return Err(Error::Format);
+            };
+            let index = *ids.get(dependency).ok_or(Error::Format)?;
+            refs.entry(provider.to_string()).or_default().push(index);
+        }
+
+        let mut providers = Vec::new();
+        for row in select(&mut package, &PROVIDER_TABLES)? {
+            let key = string(&row, "ProviderKey").ok_or(Error::Format)?;
+            let version = string(&row, "Version")
+                .or_else(|| properties.get("ProductVersion").cloned())
+                .map(|v| version(&v))
+                .transpose()
+                .map_err(|err| Error::InvalidProvider(key.clone(), Box::new(err)))?;
+
+            let requires = row[0]
+                .as_str()
+                .and_then(|id| refs.get(id))
+                .map(|indices| indices.iter().map(|&i| dependencies[i].clone()).collect())
+                .unwrap_or_default();
+
+            providers.push(PackageProvider {
+                provider: Provider {
+                    name: string(&row, "DisplayName")
+                        .or_else(|| properties.get("ProductName").cloned())
+                        .unwrap_or_default(),
+                    version: version.unwrap_or_default(),
+                    id: product_code.clone(),
+                    attributes: attributes(&row),
+                    key,
+                },
+                requires,
+            });
+        }
+
+        Ok(MsiPackage {
+            product_code,
+            providers,
+            dependencies,
+        })
+    }
+}
+
+// Selects all rows from the first of the tables that exists, or none if no table exists.
+fn select<R: Read + Seek>(package: &mut Package<R>, tables: &[&str]) -> Result<Vec<msi::Row>> {
+    match tables.iter().find(|t| package.has_table(t)) {
+        Some(table) => Ok(package.select_rows(Select::table(*table))?.collect()),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn string(row: &msi::Row, column: &str) -> Option<String> {
+    if !row.has_column(column) {
+        return None;
+    }
+    row[column].as_str().map(str::to_string)
+}
+
+fn attributes(row: &msi::Row) -> Option<Attributes> {
+    if !row.has_column("Attributes") {
+        return None;
+    }
+    row["Attributes"]
+        .as_int()
+        .filter(|&v| v != 0)
+        .map(|v| Attributes::from_bits(v as u32))
+}
+
+fn version(s: &str) -> Result<Version> {
+    Version::try_from(s).map_err(|_| Error::Format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use msi::{Column, Insert, PackageType, Value};
+    use std::io::Cursor;
+
+    fn table(
+        package: &mut Package<Cursor<Vec<u8>>>,
+        name: &str,
+        columns: Vec<Column>,
+        rows: Vec<Vec<Value>>,
+    ) {
+        package.create_table(name, columns).unwrap();
+        package.insert_rows(Insert::into(name).rows(rows)).unwrap();
+    }
+
+    fn package(prefix: &str, version: Value) -> Cursor<Vec<u8>> {
+        let mut package = Package::create(PackageType::Installer, Cursor::new(Vec::new())).unwrap();
+        table(
+            &mut package,
+            "Property",
+            vec![
+                Column::build("Property").primary_key().id_string(72),
+                Column::build("Value").text_string(0),
+            ],
+            vec![
+                vec![
+                    "ProductCode".into(),
+                    "{F8E9F4B1-0000-0000-0000-000000000000}".into(),
+                ],
+                vec!["ProductName".into(), "Example App".into()],
+                vec!["ProductVersion".into(), "1.2.3.4".into()],
+            ],
+        );
+        table(
+            &mut package,
+            &format!("{}DependencyProvider", prefix),
+            vec![
+                Column::build(format!("{}DependencyProvider", prefix))
+                    .primary_key()
+                    .id_string(72),
+                Column::build("Component_").id_string(72),
+                Column::build("ProviderKey").string(255),
+                Column::build("Version").nullable().string(72),
+                Column::build("DisplayName").nullable().string(255),
+                Column::build("Attributes").nullable().int32(),
+            ],
+            vec![vec![
+                "ProviderApp".into(),
+                "ProductComponent".into(),
+                "Example.App".into(),
+                version,
+                Value::Null,
+                Value::Null,
+            ]],
+        );
+        table(
+            &mut package,
+            &format!("{}Dependency", prefix),
+            vec![
+                Column::build(format!("{}Dependency", prefix))
+                    .primary_key()
+                    .id_string(72),
+                Column::build("ProviderKey").string(255),
+                Column::build("MinVersion").nullable().string(72),
+                Column::build("MaxVersion").nullable().string(72),
+                Column::build("Attributes").nullable().int32(),
+            ],
+            vec![
+                vec![
+                    "Runtime".into(),
+                    "Example.Runtime".into(),
+                    "2.0".into(),
+                    "3.0".into(),
+                    Value::Int(0x100),
+                ],
+                vec![
+                    "Unused".into(),
+                    "Example.Unused".into(),
+                    Value::Null,
+                    Value::Null,
+                    Value::Null,
+                ],
+            ],
+        );
+        table(
+            &mut package,
+            &format!("{}DependencyRef", prefix),
+            vec![
+                Column::build(format!("{}DependencyProvider_", prefix))
+                    .primary_key()
+                    .id_string(72),
+                Column::build(format!("{}Dependency_", prefix))
+                    .primary_key()
+                    .id_string(72),
+            ],
+            vec![vec!["ProviderApp".into(), "Runtime".into()]],
+        );
+
+        package.into_inner().unwrap()
+    }
+
+    #[test]
+    fn from_reader_v3() {
+        let package = MsiPackage::from_reader(package("Wix", Value::Null)).unwrap();
+        assert_eq!(
+            package.product_code.as_deref(),
+            Some("{F8E9F4B1-0000-0000-0000-000000000000}")
+        );
+
+        let provider = &package.providers[0];
+        assert_eq!(provider.provider.key, "Example.App");
+        assert_eq!(provider.provider.name, "Example App");
+        assert_eq!(provider.provider.version, Version::from([1, 2, 3, 4]));
+        assert_eq!(provider.provider.id, package.product_code);
+        assert_eq!(provider.provider.attributes, None);
+
+        assert_eq!(provider.requires.len(), 1);
+        assert_eq!(provider.requires[0].key, "Example.Runtime");
+        assert_eq!(
+            provider.requires[0].range().to_string(),
+            "[2.0.0.0,3.0.0.0)"
+        );
+
+        assert_eq!(package.dependencies.len(), 2);
+        assert_eq!(package.dependencies[1].range().to_string(), "(,)");
+    }
+
+    #[test]
+    fn from_reader_v4() {
+        let package = MsiPackage::from_reader(package("Wix4", "2.0".into())).unwrap();
+        assert_eq!(
+            package.providers[0].provider.version,
+            Version::from([2, 0, 0, 0])
+        );
+        assert_eq!(package.providers[0].requires[0].key, "Example.Runtime");
+    }
+
+    #[test]
+    fn from_reader_invalid_version() {
+        assert_eq!(
+            MsiPackage::from_reader(package("Wix", "[ProductVersion]".into())).unwrap_err(),
+            Error::InvalidProvider("Example.App".to_string(), Box::new(Error::Format))
+        );
+    }
+
+    #[test]
+    fn from_reader_without_tables() {
+        let package = Package::create(PackageType::Installer, Cursor::new(Vec::new()))
+            .unwrap()
+            .into_inner()
+            .unwrap();
+        assert_eq!(
+            MsiPackage::from_reader(package).unwrap(),
+            MsiPackage::default()
+        );
+    }
+
+    #[test]
+    fn from_reader_not_msi() {
+        assert!(matches!(
+            MsiPackage::from_reader(Cursor::new(b"not a package".to_vec())),
+            Err(Error::Io { .. })
+        ));
+    }
+}
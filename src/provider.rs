@@ -1,25 +1,71 @@
 // Copyright 2023 Heath Stewart.
 // Licensed under the MIT License. See LICENSE.txt in the project root for license information.
 
-use crate::registry::{Data, Key};
-use crate::version::Version;
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::version::{Version, VersionRange};
 use crate::{Attributes, Result, Scope};
 use std::{collections::HashSet, fmt::Display, hash};
-use windows::core::{w, PCWSTR};
-use windows::Win32::System::Registry::HKEY;
 
 #[derive(Debug, Default, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Dependency {
     /// Provider key that uniquely identifies the dependency.
     pub key: String,
+
+    /// Optional minimum version of the provider required by a dependent.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub min_version: Option<Version>,
+
+    /// Optional maximum version of the provider required by a dependent.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub max_version: Option<Version>,
+
+    /// Optional attributes used when checking the version range.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub attributes: Option<Attributes>,
 }
 
 impl Dependency {
     pub(crate) fn new(provider_key: impl Into<String>) -> Self {
         Dependency {
             key: provider_key.into(),
+            ..Default::default()
         }
     }
+
+    pub(crate) fn from(dependent_key: impl Into<String>, key: &impl StoreKey) -> Self {
+        // Values are optional and ignored if malformed.
+        Dependency {
+            key: dependent_key.into(),
+            min_version: key
+                .value(Some("MinVersion"))
+                .and_then(|v| v.to_version())
+                .ok(),
+            max_version: key
+                .value(Some("MaxVersion"))
+                .and_then(|v| v.to_version())
+                .ok(),
+            attributes: attributes(key),
+        }
+    }
+
+    /// Gets the range of provider versions required by the dependent.
+    pub fn range(&self) -> VersionRange {
+        VersionRange::new(
+            self.min_version,
+            self.max_version,
+            self.attributes.unwrap_or_default(),
+        )
+    }
 }
 
 impl Display for Dependency {
@@ -42,65 +88,99 @@ impl hash::Hash for Dependency {
 }
 
 #[derive(Debug, Default, Clone, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Provider {
     /// Provider key that uniquely identifies the provider.
     pub key: String,
 
     /// Optional display name of the provider.
+    #[cfg_attr(feature = "serde", serde(default))]
     pub name: String,
 
     /// Version of the provider.
     pub version: Version,
 
     /// Optional identifier of the package for an external system e.g., a ProductCode for a Windows Installer package.
-    #[allow(dead_code)] // TODO
+    ///
+    /// Use [`ProviderOwner`](crate::ProviderOwner) to find the Add/Remove Programs entry for the package.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub id: Option<String>,
 
     /// Optional attributes used when checking dependencies.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub attributes: Option<Attributes>,
 }
 
 impl Provider {
-    pub(crate) fn from(provider_key: impl Into<String>, key: &Key) -> crate::Result<Self> {
+    pub(crate) fn from(
+        provider_key: impl Into<String>,
+        key: &impl StoreKey,
+    ) -> crate::Result<Self> {
         // Equivalent to deputil:DepGetProviderInformation.
         Ok(Provider {
             key: provider_key.into(),
-            name: key.value(w!("DisplayName"))?.to_string()?,
-            version: key.value(w!("Version"))?.to_version()?,
-            id: key.value(PCWSTR::null())?.to_string().ok(),
-            ..Default::default()
+            name: match key.value(Some("DisplayName")) {
+                Err(crate::Error::NotFound) => String::new(),
+                value => value?.to_string()?,
+            },
+            version: key.value(Some("Version"))?.to_version()?,
+            id: key.value(None).and_then(|v| v.to_string()).ok(),
+            attributes: attributes(key),
         })
     }
 
     /// Checks that there are no dependents registered for the current provider that are being uninstalled.
-    pub fn check_dependents<K>(
+    pub fn check_dependents<S>(
         &self,
+        store: &S,
         scope: Scope,
         #[allow(unused_variables)] // Prevent future breaking change; not currently used.
         attributes: Option<Attributes>,
         ignore: Option<&HashSet<String>>,
-    ) -> Result<Option<Vec<Dependency>>> {
-        crate::check_dependents(&self.key, scope, attributes, ignore)
+    ) -> Result<Option<Vec<Dependency>>>
+    where
+        S: DependencyStore,
+    {
+        crate::check_dependents(store, &self.key, scope, attributes, ignore)
     }
 
     /// Registers the [`Provider`].
-    pub fn register(&self, scope: Scope) -> crate::Result<()> {
+    pub fn register<S>(&self, store: &S, scope: Scope) -> crate::Result<()>
+    where
+        S: DependencyStore,
+    {
         // Equivalent to deputil:DepRegisterDependency.
-        let key = Key::create::<HKEY, PCWSTR>(scope.into(), crate::ROOT_KEY)?;
+        store.exclusive(scope, || {
+            let key = store.create(scope)?;
+            let key = key.create_subkey(&self.key)?;
 
-        let provider_key = crate::to_pcwstr(&self.key);
-        let key = key.create_subkey(provider_key)?;
+            key.set_value(Some("DisplayName"), Data::String(self.name.to_string()))?;
+            key.set_value(Some("Version"), Data::String(self.version.to_string()))?;
+            if let Some(id) = &self.id {
+                key.set_value(None, Data::String(id.to_string()))?;
+            }
+            if let Some(attributes) = self.attributes {
+                key.set_value(Some("Attributes"), Data::DWord(attributes.bits()))?;
+            }
 
-        key.set_value(Some(w!("DisplayName")), Data::String(self.name.to_string()))?;
-        key.set_value(Some(w!("Version")), Data::String(self.version.to_string()))?;
-        if let Some(id) = &self.id {
-            key.set_value(None, Data::String(id.to_string()))?;
-        }
-        if let Some(attributes) = self.attributes {
-            key.set_value(Some(w!("Attributes")), Data::DWord(attributes as u32))?;
-        }
+            Ok(())
+        })
+    }
 
-        Ok(())
+    /// Unregisters the [`Provider`] along with any dependents registered for it.
+    ///
+    /// Returns [`Error::HasDependents`](crate::Error::HasDependents) if any dependents are still registered unless `force` is true.
+    pub fn unregister<S>(&self, store: &S, scope: Scope, force: bool) -> crate::Result<()>
+    where
+        S: DependencyStore,
+    {
+        crate::unregister_provider(store, &self.key, scope, force)
     }
 }
 
@@ -126,6 +206,55 @@ impl hash::Hash for Provider {
     }
 }
 
+/// An iterator over all providers registered in a scope.
+///
+/// Providers that cannot be read are yielded as [`Error::InvalidProvider`](crate::Error::InvalidProvider)
+/// so that enumeration can continue past partially-written or malformed keys.
+pub struct Providers<K> {
+    key: Option<K>,
+    names: std::vec::IntoIter<String>,
+}
+
+impl<K: StoreKey> Providers<K> {
+    pub(crate) fn new(key: Option<K>) -> Result<Self> {
+        let names = match &key {
+            Some(key) => key.keys()?,
+            None => Vec::new(),
+        };
+
+        Ok(Providers {
+            key,
+            names: names.into_iter(),
+        })
+    }
+}
+
+impl<K: StoreKey> Iterator for Providers<K> {
+    type Item = Result<Provider>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.key.as_ref()?;
+        let name = self.names.next()?;
+
+        Some(
+            key.open_subkey(&name)
+                .and_then(|k| Provider::from(&name, &k))
+                .map_err(|err| crate::Error::InvalidProvider(name, Box::new(err))),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.names.size_hint()
+    }
+}
+
+fn attributes(key: &impl StoreKey) -> Option<Attributes> {
+    match key.value(Some("Attributes")) {
+        Ok(Data::DWord(attributes)) => Some(Attributes::from_bits(attributes)),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,4 +279,88 @@ mod tests {
             "display (test)"
         );
     }
+
+    #[test]
+    fn provider_register_round_trip() {
+        let store = crate::MemoryStore::new();
+        let expected = Provider {
+            key: "test".to_string(),
+            name: "display".to_string(),
+            version: Version::from([1, 2, 3, 4]),
+            id: Some("{00000000-0000-0000-0000-000000000000}".to_string()),
+            attributes: Some(Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE),
+        };
+        expected.register(&store, Scope::Machine).unwrap();
+
+        let actual = crate::get_provider(&store, "test", Scope::Machine).unwrap();
+        assert_eq!(actual.name, expected.name);
+        assert_eq!(actual.version, expected.version);
+        assert_eq!(actual.id, expected.id);
+        assert_eq!(actual.attributes, expected.attributes);
+    }
+
+    #[test]
+    fn provider_without_attributes() {
+        let store = crate::MemoryStore::new();
+        Provider {
+            key: "test".to_string(),
+            ..Default::default()
+        }
+        .register(&store, Scope::Machine)
+        .unwrap();
+
+        let actual = crate::get_provider(&store, "test", Scope::Machine).unwrap();
+        assert_eq!(actual.attributes, None);
+    }
+
+    #[test]
+    fn provider_without_name() {
+        let store = crate::MemoryStore::new();
+        let key = store
+            .create(Scope::Machine)
+            .and_then(|k| k.create_subkey("test"))
+            .unwrap();
+        key.set_value(Some("Version"), Data::String("1.0".to_string()))
+            .unwrap();
+
+        let actual = crate::get_provider(&store, "test", Scope::Machine).unwrap();
+        assert_eq!(actual.name, "");
+        assert_eq!(actual.version, Version::from([1, 0, 0, 0]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn provider_serde() {
+        let provider = Provider {
+            key: "test".to_string(),
+            name: "display".to_string(),
+            version: Version::from([1, 2, 3, 4]),
+            id: None,
+            attributes: Some(Attributes::BUNDLE),
+        };
+        let json = serde_json::to_string(&provider).unwrap();
+        assert_eq!(
+            json,
+            r#"{"key":"test","name":"display","version":"1.2.3.4","attributes":"Bundle"}"#
+        );
+
+        let actual: Provider = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual.version, provider.version);
+        assert_eq!(actual.attributes, provider.attributes);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn dependency_serde() {
+        let dependency = Dependency {
+            min_version: Some(Version::from([1, 0, 0, 0])),
+            ..Dependency::new("test")
+        };
+        let json = serde_json::to_string(&dependency).unwrap();
+        assert_eq!(json, r#"{"key":"test","min_version":"1.0.0.0"}"#);
+
+        let actual: Dependency = serde_json::from_str(&json).unwrap();
+        assert_eq!(actual.min_version, dependency.min_version);
+        assert_eq!(actual.max_version, None);
+    }
 }
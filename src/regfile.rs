@@ -0,0 +1,693 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::store::{DependencyStore, StoreKey};
+use crate::{Data, Error, Result, Scope};
+
+// cspell:ignore HKCU HKLM REGEDIT
+
+const HEADER: &str = "Windows Registry Editor Version 5.00";
+const ROOT_PATH: &str = r"Software\Classes\Installer\Dependencies";
+
+// Registry Editor wraps hex data after this many columns.
+const LINE_WIDTH: usize = 76;
+
+/// Exports the dependency tree of each of the `scopes` in the Windows Registry Editor 5.00 `.reg` format.
+///
+/// Scopes without a root key are omitted. The returned text should be encoded as UTF-16LE with a byte order mark
+/// before writing it to a file that Registry Editor will import.
+pub fn export_reg<S>(store: &S, scopes: &[Scope]) -> Result<String>
+where
+    S: DependencyStore,
+{
+    let mut out = String::new();
+    writeln!(out, "{}", HEADER).unwrap();
+    writeln!(out).unwrap();
+
+    for scope in BTreeSet::from_iter(scopes.iter().copied()) {
+        if let Some(key) = crate::open_root(store, scope)? {
+            let path = format!(r"{}\{}", hive(scope), ROOT_PATH);
+            write_key(&mut out, &path, &key)?;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Imports the dependency tree from text in the Windows Registry Editor 5.00 `.reg` format.
+///
+/// Only keys within `Software\Classes\Installer\Dependencies` under `HKEY_CURRENT_USER` or `HKEY_LOCAL_MACHINE` are
+/// imported; all other keys and their values are ignored. Keys and values prefixed with `-` are deleted.
+/// The text is parsed completely before any changes are made, and syntax errors are returned as [`Error::Parse`].
+/// Changes are made with [exclusive](DependencyStore::exclusive) access to each scope they touch, and like other
+/// operations, changes made before an error are kept.
+pub fn import_reg<S>(store: &S, s: &str) -> Result<()>
+where
+    S: DependencyStore,
+{
+    let ops = parse(s)?;
+    let scopes = BTreeSet::from_iter(ops.iter().map(Op::scope));
+    let scopes = Vec::from_iter(scopes);
+
+    exclusive(
+        store,
+        &scopes,
+        Box::new(|| {
+            for op in ops {
+                apply(store, op)?;
+            }
+            Ok(())
+        }),
+    )
+}
+
+// Runs `f` with exclusive access to each of the `scopes`, which are always locked in the same order.
+fn exclusive<'a, S: DependencyStore>(
+    store: &S,
+    scopes: &[Scope],
+    f: Box<dyn FnOnce() -> Result<()> + 'a>,
+) -> Result<()> {
+    match scopes.split_first() {
+        Some((&scope, rest)) => store.exclusive(scope, || exclusive(store, rest, f)),
+        None => f(),
+    }
+}
+
+fn apply<S: DependencyStore>(store: &S, op: Op) -> Result<()> {
+    match op {
+        Op::CreateKey(scope, path) => {
+            create(store, scope, &path)?;
+        }
+        Op::DeleteKey(scope, path) => delete(store, scope, &path)?,
+        Op::SetValue(scope, path, name, data) => {
+            create(store, scope, &path)?.set_value(name.as_deref(), data)?
+        }
+        Op::DeleteValue(scope, path, name) => {
+            if let Some(key) = open(store, scope, &path)? {
+                match key.delete_value(name.as_deref()) {
+                    Err(Error::NotFound) => {}
+                    err => err?,
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Op {
+    CreateKey(Scope, Vec<String>),
+    DeleteKey(Scope, Vec<String>),
+    SetValue(Scope, Vec<String>, Option<String>, Data),
+    DeleteValue(Scope, Vec<String>, Option<String>),
+}
+
+impl Op {
+    fn scope(&self) -> Scope {
+        match self {
+            Op::CreateKey(scope, ..)
+            | Op::DeleteKey(scope, ..)
+            | Op::SetValue(scope, ..)
+            | Op::DeleteValue(scope, ..) => *scope,
+        }
+    }
+}
+
+fn hive(scope: Scope) -> &'static str {
+    match scope {
+        Scope::User => "HKEY_CURRENT_USER",
+        Scope::Machine => "HKEY_LOCAL_MACHINE",
+    }
+}
+
+fn write_key(out: &mut String, path: &str, key: &impl StoreKey) -> Result<()> {
+    writeln!(out, "[{}]", path).unwrap();
+
+    let mut values = key.values()?;
+    values.sort_by_key(|(name, _)| name.is_some());
+    for (name, data) in values {
+        let name = match name {
+            Some(name) => format!("\"{}\"", escape(&name)),
+            None => "@".to_string(),
+        };
+        write_value(out, &name, &data);
+    }
+    writeln!(out).unwrap();
+
+    for name in key.keys()? {
+        let subkey = match key.open_subkey(&name) {
+            Err(Error::NotFound) => continue,
+            err => err,
+        }?;
+        write_key(out, &format!(r"{}\{}", path, name), &subkey)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn write_value(out: &mut String, name: &str, data: &Data) {
+    let (prefix, bytes) = match data {
+        Data::String(s) if !s.contains(['\r', '\n', '\0']) => {
+            writeln!(out, "{}=\"{}\"", name, escape(s)).unwrap();
+            return;
+        }
+        Data::DWord(v) => {
+            writeln!(out, "{}=dword:{:08x}", name, v).unwrap();
+            return;
+        }
+        Data::String(s) => ("hex(1)", to_utf16(s.split('\0'))),
+        Data::Binary(v) => ("hex", v.clone()),
+        Data::MultiString(v) => ("hex(7)", to_utf16(v.iter().map(String::as_str).chain([""]))),
+        Data::QWord(v) => ("hex(b)", v.to_le_bytes().to_vec()),
+    };
+
+    let mut line = format!("{}={}:", name, prefix);
+    for (i, byte) in bytes.iter().enumerate() {
+        write!(line, "{:02x}", byte).unwrap();
+        if i + 1 < bytes.len() {
+            line.push(',');
+            if line.len() > LINE_WIDTH {
+                writeln!(out, "{}\\", line).unwrap();
+                line = "  ".to_string();
+            }
+        }
+    }
+    writeln!(out, "{}", line).unwrap();
+}
+
+// Encodes strings as UTF-16LE with each followed by a null character.
+fn to_utf16<'a>(strings: impl Iterator<Item = &'a str>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for s in strings {
+        for c in s.encode_utf16().chain([0]) {
+            bytes.extend(c.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+fn from_utf16(bytes: &[u8]) -> Option<Vec<String>> {
+    let chunks = bytes.chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+
+    let chars: Vec<u16> = chunks.map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    let chars = chars.strip_suffix(&[0]).unwrap_or(&chars);
+    chars
+        .split(|&c| c == 0)
+        .map(|s| String::from_utf16(s).ok())
+        .collect()
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('"', "\\\"")
+}
+
+fn create<S: DependencyStore>(store: &S, scope: Scope, path: &[String]) -> Result<S::Key> {
+    let mut key = store.create(scope)?;
+    for name in path {
+        key = key.create_subkey(name)?;
+    }
+    Ok(key)
+}
+
+fn open<S: DependencyStore>(store: &S, scope: Scope, path: &[String]) -> Result<Option<S::Key>> {
+    let Some(mut key) = crate::open_root(store, scope)? else {
+        return Ok(None);
+    };
+    for name in path {
+        key = match key.open_subkey(name) {
+            Err(Error::NotFound) => return Ok(None),
+            err => err?,
+        };
+    }
+    Ok(Some(key))
+}
+
+fn delete<S: DependencyStore>(store: &S, scope: Scope, path: &[String]) -> Result<()> {
+    let Some((name, parent)) = path.split_last() else {
+        // The root key itself cannot be deleted, so remove everything within it.
+        if let Some(key) = crate::open_root(store, scope)? {
+            for name in key.keys()? {
+                key.delete_subkey(&name)?;
+            }
+            for (name, _) in key.values()? {
+                key.delete_value(name.as_deref())?;
+            }
+        }
+        return Ok(());
+    };
+
+    if let Some(key) = open(store, scope, parent)? {
+        match key.delete_subkey(name) {
+            Err(Error::NotFound) => {}
+            err => err?,
+        }
+    }
+    Ok(())
+}
+
+fn parse(s: &str) -> Result<Vec<Op>> {
+    let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+    let mut ops = Vec::new();
+    let mut header = false;
+
+    // The current key, or `None` if outside the dependency tree.
+    let mut current: Option<Option<(Scope, Vec<String>)>> = None;
+
+    let mut lines = s.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let mut line = line.trim().to_string();
+
+        // Hex data continues onto following lines after a trailing comma and backslash.
+        while line.ends_with(",\\") {
+            line.pop();
+            match lines.next() {
+                Some((_, next)) => line.push_str(next.trim()),
+                None => break,
+            }
+        }
+
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+
+        if !header {
+            if line != HEADER {
+                return Err(error(line_number, 1, "expected registry editor header"));
+            }
+            header = true;
+            continue;
+        }
+
+        if let Some(path) = line.strip_prefix('[') {
+            let Some(path) = path.strip_suffix(']') else {
+                return Err(error(line_number, line.len(), "expected ']'"));
+            };
+            let (path, deleted) = match path.strip_prefix('-') {
+                Some(path) => (path, true),
+                None => (path, false),
+            };
+
+            let key = key(path);
+            if let Some((scope, path)) = &key {
+                ops.push(if deleted {
+                    Op::DeleteKey(*scope, path.clone())
+                } else {
+                    Op::CreateKey(*scope, path.clone())
+                });
+            }
+
+            // Values of deleted keys are ignored like Registry Editor does.
+            current = Some(if deleted { None } else { key });
+            continue;
+        }
+
+        let Some(key) = &current else {
+            return Err(error(line_number, 1, "expected a key before values"));
+        };
+
+        let (name, rest) = if let Some(rest) = line.strip_prefix('@') {
+            (None, rest)
+        } else if let Some(rest) = line.strip_prefix('"') {
+            let (name, rest) = unescape(rest).ok_or_else(|| {
+                error(
+                    line_number,
+                    1,
+                    "expected a closing quote after the value name",
+                )
+            })?;
+            (Some(name), rest)
+        } else {
+            return Err(error(line_number, 1, "expected a value name"));
+        };
+
+        let column = line.len() - rest.len() + 1;
+        let Some(rest) = rest.trim_start().strip_prefix('=') else {
+            return Err(error(line_number, column, "expected '='"));
+        };
+        let column = line.len() - rest.len() + 1;
+        let data =
+            parse_data(rest.trim()).map_err(|message| error(line_number, column, message))?;
+
+        if let Some((scope, path)) = key {
+            ops.push(match data {
+                Some(data) => Op::SetValue(*scope, path.clone(), name, data),
+                None => Op::DeleteValue(*scope, path.clone(), name),
+            });
+        }
+    }
+
+    if !header {
+        return Err(error(1, 1, "expected registry editor header"));
+    }
+
+    Ok(ops)
+}
+
+// Gets the scope and path relative to the root key, or `None` if outside the dependency tree.
+fn key(path: &str) -> Option<(Scope, Vec<String>)> {
+    let (hive, path) = path.split_once('\\').unwrap_or((path, ""));
+    let scope = match hive.to_uppercase().as_str() {
+        "HKEY_CURRENT_USER" | "HKCU" => Scope::User,
+        "HKEY_LOCAL_MACHINE" | "HKLM" => Scope::Machine,
+        _ => return None,
+    };
+
+    let mut names = path.split('\\').filter(|s| !s.is_empty());
+    for expected in ROOT_PATH.split('\\') {
+        if !names.next()?.eq_ignore_ascii_case(expected) {
+            return None;
+        }
+    }
+
+    Some((scope, names.map(str::to_string).collect()))
+}
+
+// Parses data following '=', or `None` if the value is deleted.
+pub(crate) fn parse_data(s: &str) -> std::result::Result<Option<Data>, String> {
+    if s == "-" {
+        return Ok(None);
+    }
+
+    if let Some(rest) = s.strip_prefix('"') {
+        return match unescape(rest) {
+            Some((value, "")) => Ok(Some(Data::String(value))),
+            Some(_) => Err("unexpected content after string".to_string()),
+            None => Err("expected a closing quote".to_string()),
+        };
+    }
+
+    if let Some(rest) = s.strip_prefix("dword:") {
+        return match u32::from_str_radix(rest, 16) {
+            Ok(value) if rest.len() <= 8 => Ok(Some(Data::DWord(value))),
+            _ => Err(format!("invalid dword {:?}", rest)),
+        };
+    }
+
+    let Some(rest) = s.strip_prefix("hex") else {
+        return Err(format!("unsupported data {:?}", s));
+    };
+    let (kind, bytes) = match rest.strip_prefix('(') {
+        Some(rest) => rest
+            .split_once("):")
+            .and_then(|(kind, bytes)| Some((u32::from_str_radix(kind, 16).ok()?, bytes))),
+        None => rest.strip_prefix(':').map(|bytes| (3, bytes)),
+    }
+    .ok_or_else(|| format!("invalid hex data {:?}", s))?;
+
+    let bytes = bytes
+        .split(',')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(|b| u8::from_str_radix(b, 16).ok().filter(|_| b.len() <= 2))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("invalid hex data {:?}", s))?;
+
+    let invalid = || format!("invalid hex({:x}) data", kind);
+    let data = match kind {
+        // REG_SZ and REG_EXPAND_SZ
+        1 | 2 => Data::String(from_utf16(&bytes).ok_or_else(invalid)?.join("\0")),
+        3 => Data::Binary(bytes),
+        4 => Data::DWord(u32::from_le_bytes(bytes.try_into().map_err(|_| invalid())?)),
+        7 => {
+            let mut strings = from_utf16(&bytes).ok_or_else(invalid)?;
+            if strings.last().is_some_and(String::is_empty) {
+                strings.pop();
+            }
+            Data::MultiString(strings)
+        }
+        0xb => Data::QWord(u64::from_le_bytes(bytes.try_into().map_err(|_| invalid())?)),
+        _ => return Err(format!("unsupported value type hex({:x})", kind)),
+    };
+
+    Ok(Some(data))
+}
+
+// Unescapes a string following its opening quote, returning the string and any content after the closing quote.
+fn unescape(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[i + 1..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+pub(crate) fn error(line: usize, column: usize, message: impl Into<String>) -> Error {
+    Error::Parse {
+        line,
+        column,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_provider, register_dependent, MemoryKey, MemoryStore, Provider, Version};
+    use std::cell::RefCell;
+
+    fn store() -> MemoryStore {
+        let store = MemoryStore::new();
+        Provider {
+            key: "runtime".to_string(),
+            name: "Runtime \"x64\"".to_string(),
+            version: Version::from([1, 2, 3, 4]),
+            id: Some("{F8E9F4B1-0000-0000-0000-000000000000}".to_string()),
+            ..Default::default()
+        }
+        .register(&store, Scope::Machine)
+        .unwrap();
+        register_dependent(
+            &store,
+            "runtime",
+            "bundle",
+            Scope::Machine,
+            Some(Version::from([1, 0, 0, 0])),
+            None,
+            Some(crate::Attributes::MIN_VERSION_INCLUSIVE),
+        )
+        .unwrap();
+        store
+    }
+
+    #[test]
+    fn export() {
+        let text = export_reg(&store(), &[Scope::Machine, Scope::User]).unwrap();
+        assert_eq!(
+            text,
+            r#"Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\Software\Classes\Installer\Dependencies]
+
+[HKEY_LOCAL_MACHINE\Software\Classes\Installer\Dependencies\runtime]
+@="{F8E9F4B1-0000-0000-0000-000000000000}"
+"DisplayName"="Runtime \"x64\""
+"Version"="1.2.3.4"
+
+[HKEY_LOCAL_MACHINE\Software\Classes\Installer\Dependencies\runtime\Dependents]
+
+[HKEY_LOCAL_MACHINE\Software\Classes\Installer\Dependencies\runtime\Dependents\bundle]
+"Attributes"=dword:00000100
+"MinVersion"="1.0.0.0"
+
+"#
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let store = store();
+        let key = store
+            .create(Scope::User)
+            .unwrap()
+            .create_subkey("data")
+            .unwrap();
+        key.set_value(
+            Some("Multi"),
+            Data::MultiString(vec!["a".to_string(), "b".to_string()]),
+        )
+        .unwrap();
+        key.set_value(Some("QWord"), Data::QWord(0x0102030405060708))
+            .unwrap();
+        key.set_value(Some("Binary"), Data::Binary((0..40).collect()))
+            .unwrap();
+        key.set_value(Some("Lines"), Data::String("a\r\nb".to_string()))
+            .unwrap();
+
+        let text = export_reg(&store, &[Scope::User, Scope::Machine]).unwrap();
+        assert!(text.contains("\"QWord\"=hex(b):08,07,06,05,04,03,02,01\n"));
+        assert!(text.contains("\"Multi\"=hex(7):61,00,00,00,62,00,00,00,00,00\n"));
+        assert!(text.contains(",\\\n  "));
+
+        let imported = MemoryStore::new();
+        import_reg(&imported, &text).unwrap();
+        assert_eq!(
+            export_reg(&imported, &[Scope::User, Scope::Machine]).unwrap(),
+            text
+        );
+
+        let provider = get_provider(&imported, "runtime", Scope::Machine).unwrap();
+        assert_eq!(provider.version, Version::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn import() {
+        let store = store();
+        import_reg(
+            &store,
+            "\u{feff}Windows Registry Editor Version 5.00\r
+\r
+; Keys outside the dependency tree are ignored.\r
+[HKEY_LOCAL_MACHINE\\Software\\Example]\r
+\"Ignored\"=\"value\"\r
+\r
+[HKLM\\SOFTWARE\\Classes\\Installer\\Dependencies\\runtime]\r
+\"Version\"=\"2.0\"\r
+\"DisplayName\"=-\r
+\r
+[-HKEY_LOCAL_MACHINE\\Software\\Classes\\Installer\\Dependencies\\runtime\\Dependents]\r
+\r
+[HKEY_CURRENT_USER\\Software\\Classes\\Installer\\Dependencies\\tools]\r
+\"Version\"=hex(1):31,00,2e,00,30,00,00,00\r
+\"Attributes\"=hex(4):00,00,01,00\r
+",
+        )
+        .unwrap();
+
+        let provider = get_provider(&store, "runtime", Scope::Machine).unwrap();
+        assert_eq!(provider.version, Version::from([2, 0, 0, 0]));
+        assert_eq!(provider.name, "");
+        assert!(
+            crate::check_dependents(&store, "runtime", Scope::Machine, None, None)
+                .unwrap()
+                .is_none()
+        );
+
+        let provider = get_provider(&store, "tools", Scope::User).unwrap();
+        assert_eq!(provider.version, Version::from([1, 0, 0, 0]));
+        assert_eq!(provider.attributes, Some(crate::Attributes::BUNDLE));
+        assert!(store
+            .open(Scope::Machine)
+            .unwrap()
+            .open_subkey("Example")
+            .is_err());
+    }
+
+    #[test]
+    fn import_delete_root() {
+        let store = store();
+        import_reg(
+            &store,
+            "Windows Registry Editor Version 5.00\n\n[-HKEY_LOCAL_MACHINE\\Software\\Classes\\Installer\\Dependencies]\n",
+        )
+        .unwrap();
+        assert!(store
+            .open(Scope::Machine)
+            .unwrap()
+            .keys()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn import_errors() {
+        let store = store();
+        let err = |s: &str| import_reg(&store, s).unwrap_err();
+
+        assert_eq!(
+            err("REGEDIT4\n"),
+            error(1, 1, "expected registry editor header")
+        );
+        assert_eq!(
+            err("Windows Registry Editor Version 5.00\n\"a\"=\"b\"\n"),
+            error(2, 1, "expected a key before values")
+        );
+        assert_eq!(
+            err("Windows Registry Editor Version 5.00\n[HKCU\\Software\\Classes\\Installer\\Dependencies\\a]\n\"v\"=dword:xyz\n"),
+            error(3, 5, "invalid dword \"xyz\"")
+        );
+        assert_eq!(
+            err("Windows Registry Editor Version 5.00\n[HKCU\\Software\\Classes\\Installer\\Dependencies\\a]\n\"v\"=hex(b):01,02\n"),
+            error(3, 5, "invalid hex(b) data")
+        );
+
+        // Nothing is imported when any line is invalid.
+        assert!(store.open(Scope::User).is_err());
+    }
+
+    #[test]
+    fn import_later_line_fails() {
+        let store = Locking(store(), RefCell::new(Vec::new()));
+        assert_eq!(
+            import_reg(
+                &store,
+                "Windows Registry Editor Version 5.00
+
+[HKEY_LOCAL_MACHINE\\Software\\Classes\\Installer\\Dependencies\\runtime]
+\"Version\"=\"2.0\"
+
+[HKEY_CURRENT_USER\\Software\\Classes\\Installer\\Dependencies\\tools]
+\"Version\"=\"1.0\"
+",
+            )
+            .unwrap_err(),
+            Error::NotSupported
+        );
+
+        // Each scope is locked once in the same order, and changes before the error are kept.
+        assert_eq!(*store.1.borrow(), vec![Scope::User, Scope::Machine]);
+        let provider = get_provider(&store.0, "runtime", Scope::Machine).unwrap();
+        assert_eq!(provider.version, Version::from([2, 0, 0, 0]));
+    }
+
+    // A store that records the scopes locked exclusively and cannot create the user scope.
+    struct Locking(MemoryStore, RefCell<Vec<Scope>>);
+
+    impl DependencyStore for Locking {
+        type Key = MemoryKey;
+
+        fn open(&self, scope: Scope) -> Result<MemoryKey> {
+            self.0.open(scope)
+        }
+
+        fn create(&self, scope: Scope) -> Result<MemoryKey> {
+            match scope {
+                Scope::User => Err(Error::NotSupported),
+                Scope::Machine => self.0.create(scope),
+            }
+        }
+
+        fn exclusive<T>(&self, scope: Scope, f: impl FnOnce() -> Result<T>) -> Result<T> {
+            self.1.borrow_mut().push(scope);
+            f()
+        }
+    }
+
+    #[test]
+    fn key_path() {
+        assert_eq!(
+            key(r"HKEY_CURRENT_USER\Software\Classes\Installer\Dependencies\a\Dependents"),
+            Some((Scope::User, vec!["a".to_string(), "Dependents".to_string()]))
+        );
+        assert_eq!(
+            key(r"HKEY_LOCAL_MACHINE\Software\Classes\Installer\Dependencies"),
+            Some((Scope::Machine, vec![]))
+        );
+        assert_eq!(key(r"HKEY_LOCAL_MACHINE\Software\Classes\Installer"), None);
+        assert_eq!(
+            key(r"HKEY_CLASSES_ROOT\Software\Classes\Installer\Dependencies"),
+            None
+        );
+    }
+}
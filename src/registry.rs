@@ -4,7 +4,7 @@
 use std::fmt::Display;
 
 use windows::{
-    core::{IntoParam, Result, HRESULT, PCWSTR, PWSTR},
+    core::{w, IntoParam, Result, HRESULT, HSTRING, PCWSTR, PWSTR},
     Win32::{
         Foundation::{ERROR_FILE_NOT_FOUND, ERROR_INVALID_DATA, ERROR_MORE_DATA},
         System::Registry::{self, *},
@@ -12,13 +12,56 @@ use windows::{
 };
 
 use crate::error::Error;
-use crate::version::Version;
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::Scope;
 pub use Registry::HKEY_CURRENT_USER;
 pub use Registry::HKEY_LOCAL_MACHINE;
 
 pub const E_FILE_NOT_FOUND: HRESULT = HRESULT((0x80070000u32 | ERROR_FILE_NOT_FOUND.0) as i32);
 const E_INVALID_DATA: HRESULT = HRESULT((0x80070000u32 | ERROR_INVALID_DATA.0) as i32);
 
+const ROOT_KEY: PCWSTR = w!("Software\\Classes\\Installer\\Dependencies");
+const UNINSTALL_KEY: PCWSTR = w!("Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall");
+
+/// The Windows registry under `HKEY_CURRENT_USER` or `HKEY_LOCAL_MACHINE` depending on the [`Scope`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WindowsRegistry;
+
+impl DependencyStore for WindowsRegistry {
+    type Key = Key;
+
+    fn open(&self, scope: Scope) -> crate::Result<Key> {
+        Key::open::<HKEY, PCWSTR>(scope.into(), ROOT_KEY).map_err(map_registry_error)
+    }
+
+    fn create(&self, scope: Scope) -> crate::Result<Key> {
+        Key::create::<HKEY, PCWSTR>(scope.into(), ROOT_KEY).map_err(map_registry_error)
+    }
+
+    fn open_uninstall(&self, scope: Scope) -> crate::Result<Vec<Key>> {
+        // HKEY_CURRENT_USER\Software is shared by both views.
+        let views: &[REG_SAM_FLAGS] = match scope {
+            Scope::User => &[KEY_WOW64_64KEY],
+            Scope::Machine => &[KEY_WOW64_64KEY, KEY_WOW64_32KEY],
+        };
+
+        let mut keys = Vec::new();
+        for &view in views {
+            match Key::open_with_access::<HKEY, PCWSTR>(
+                scope.into(),
+                UNINSTALL_KEY,
+                KEY_READ | view,
+            )
+            .map_err(map_registry_error)
+            {
+                Err(crate::Error::NotFound) => continue,
+                key => keys.push(key?),
+            }
+        }
+        Ok(keys)
+    }
+}
+
 #[derive(Debug)]
 pub struct Key {
     handle: HKEY,
@@ -48,19 +91,27 @@ impl Key {
     }
 
     pub fn open<K, P>(key: K, path: P) -> Result<Self>
+    where
+        K: IntoParam<HKEY>,
+        P: IntoParam<PCWSTR>,
+    {
+        Key::open_with_access(key, path, KEY_READ)
+    }
+
+    /// Opens a key with the given access, which is also used to open subkeys e.g., to select a registry view.
+    pub fn open_with_access<K, P>(key: K, path: P, access: REG_SAM_FLAGS) -> Result<Self>
     where
         K: IntoParam<HKEY>,
         P: IntoParam<PCWSTR>,
     {
         unsafe {
-            const ACCESS: REG_SAM_FLAGS = KEY_READ;
             let mut handle: HKEY = Default::default();
 
             let path: PCWSTR = path.into_param().abi();
-            RegOpenKeyExW(key, path, 0, ACCESS, &mut handle)?;
+            RegOpenKeyExW(key, path, 0, access, &mut handle)?;
             Ok(Key {
                 handle,
-                access: ACCESS,
+                access,
                 name: get_name(path),
             })
         }
@@ -104,7 +155,6 @@ impl Key {
         Keys::new(&self.handle)
     }
 
-    #[allow(dead_code)]
     pub fn values(&self) -> Result<Values<'_>> {
         Values::new(&self.handle)
     }
@@ -180,6 +230,58 @@ impl Drop for Key {
     }
 }
 
+impl StoreKey for Key {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open_subkey(&self, name: &str) -> crate::Result<Self> {
+        Key::open_subkey(self, &HSTRING::from(name)).map_err(map_registry_error)
+    }
+
+    fn create_subkey(&self, name: &str) -> crate::Result<Self> {
+        Key::create_subkey(self, &HSTRING::from(name)).map_err(map_registry_error)
+    }
+
+    fn delete_subkey(&self, name: &str) -> crate::Result<()> {
+        unsafe { RegDeleteTreeW(self.handle, &HSTRING::from(name)) }.map_err(map_registry_error)
+    }
+
+    fn keys(&self) -> crate::Result<Vec<String>> {
+        Ok(Key::keys(self)
+            .map_err(map_registry_error)?
+            .map(|k| k.name.clone())
+            .collect())
+    }
+
+    fn values(&self) -> crate::Result<Vec<(Option<String>, Data)>> {
+        Ok(Key::values(self)
+            .map_err(map_registry_error)?
+            .map(|v| (v.name.filter(|name| !name.is_empty()), v.data))
+            .collect())
+    }
+
+    fn value(&self, name: Option<&str>) -> crate::Result<Data> {
+        match name {
+            Some(name) => Key::value(self, &HSTRING::from(name)),
+            None => Key::value(self, PCWSTR::null()),
+        }
+        .map(|v| v.data)
+        .map_err(map_registry_error)
+    }
+
+    fn set_value(&self, name: Option<&str>, data: Data) -> crate::Result<()> {
+        let name = name.map(HSTRING::from);
+        Key::set_value(self, name.as_ref().map(to_pcwstr), data).map_err(map_registry_error)
+    }
+
+    fn delete_value(&self, name: Option<&str>) -> crate::Result<()> {
+        let name = name.map(HSTRING::from);
+        let name = name.as_ref().map(to_pcwstr).unwrap_or_else(PCWSTR::null);
+        unsafe { RegDeleteValueW(self.handle, name) }.map_err(map_registry_error)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Value {
     pub name: Option<String>,
@@ -193,31 +295,6 @@ impl Value {
             data: Data::from(data, data_type)?,
         })
     }
-
-    pub(crate) fn to_string(&self) -> crate::Result<String> {
-        if let Data::String(s) = &self.data {
-            return Ok(s.clone());
-        }
-
-        Err(Error::Format)
-    }
-
-    pub(crate) fn to_version(&self) -> crate::Result<Version> {
-        match &self.data {
-            Data::String(s) => Version::try_from(s.as_str()),
-            Data::QWord(d) => Ok(Version::from(*d)),
-            _ => Err(Error::Format),
-        }
-    }
-}
-
-#[derive(Debug, PartialEq)]
-pub enum Data {
-    Binary(Vec<u8>),
-    DWord(u32),
-    MultiString(Vec<String>),
-    QWord(u64),
-    String(String),
 }
 
 impl Data {
@@ -461,6 +538,19 @@ pub(crate) fn map_registry_error(err: windows::core::Error) -> Error {
     }
 }
 
+impl From<Scope> for HKEY {
+    fn from(value: Scope) -> Self {
+        match value {
+            Scope::User => HKEY_CURRENT_USER,
+            Scope::Machine => HKEY_LOCAL_MACHINE,
+        }
+    }
+}
+
+fn to_pcwstr(value: &HSTRING) -> PCWSTR {
+    PCWSTR::from_raw(value.as_ptr())
+}
+
 fn get_name<K>(path: K) -> String
 where
     K: IntoParam<PCWSTR>,
@@ -481,7 +571,14 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use windows::core::w;
+
+    #[test]
+    fn test_to_pcwstr() {
+        let value = HSTRING::from("test");
+        let value = to_pcwstr(&value);
+        let (_, value, _) = unsafe { value.as_wide().align_to::<u8>() };
+        assert_eq!(value, b"t\0e\0s\0t\0");
+    }
 
     #[test]
     fn get_name_terminated() {
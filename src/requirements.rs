@@ -0,0 +1,228 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::fmt::Display;
+
+use crate::store::DependencyStore;
+use crate::{DependencyCheck, Result, Scope, VersionRange};
+
+/// A provider that must be registered within a range of versions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Requirement {
+    /// Provider key of the required dependency.
+    pub key: String,
+
+    /// Required range of provider versions.
+    pub range: VersionRange,
+
+    /// Scope in which the provider must be registered.
+    pub scope: Scope,
+}
+
+impl Requirement {
+    /// Creates a requirement for a provider within a range of versions.
+    pub fn new(key: impl Into<String>, range: VersionRange, scope: Scope) -> Self {
+        Requirement {
+            key: key.into(),
+            range,
+            scope,
+        }
+    }
+}
+
+/// A collection of [`Requirement`] checked together.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Requirements(Vec<Requirement>);
+
+impl Requirements {
+    /// Creates an empty collection.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a requirement.
+    pub fn push(&mut self, requirement: Requirement) {
+        self.0.push(requirement);
+    }
+
+    /// Gets the number of requirements.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Gets whether there are no requirements.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets an iterator over the requirements.
+    pub fn iter(&self) -> std::slice::Iter<'_, Requirement> {
+        self.0.iter()
+    }
+
+    /// Checks every requirement, opening the root key of each scope only once.
+    ///
+    /// Unsatisfied requirements are reported rather than returned as errors.
+    pub fn check_all<S>(&self, store: &S) -> Result<Report>
+    where
+        S: DependencyStore,
+    {
+        let mut user = None;
+        let mut machine = None;
+        let mut report = Report::default();
+
+        for requirement in &self.0 {
+            let root = match requirement.scope {
+                Scope::User => &mut user,
+                Scope::Machine => &mut machine,
+            };
+            let root = match root {
+                Some(root) => root,
+                None => root.insert(crate::open_root(store, requirement.scope)?),
+            };
+
+            let check = DependencyCheck::check(
+                root.as_ref(),
+                &requirement.key,
+                requirement.scope,
+                requirement.range,
+            )?;
+            if check.is_satisfied() {
+                report.satisfied.push(check);
+            } else {
+                report.unsatisfied.push(check);
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl Extend<Requirement> for Requirements {
+    fn extend<T: IntoIterator<Item = Requirement>>(&mut self, iter: T) {
+        self.0.extend(iter)
+    }
+}
+
+impl FromIterator<Requirement> for Requirements {
+    fn from_iter<T: IntoIterator<Item = Requirement>>(iter: T) -> Self {
+        Requirements(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Requirements {
+    type Item = Requirement;
+    type IntoIter = std::vec::IntoIter<Requirement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Requirements {
+    type Item = &'a Requirement;
+    type IntoIter = std::slice::Iter<'a, Requirement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// The outcome of checking [`Requirements`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+    /// Checks of requirements that are satisfied.
+    pub satisfied: Vec<DependencyCheck>,
+
+    /// Checks of requirements that are not satisfied.
+    pub unsatisfied: Vec<DependencyCheck>,
+}
+
+impl Report {
+    /// Gets whether all requirements are satisfied.
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for check in &self.unsatisfied {
+            writeln!(f, "{}", check)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryStore, Provider, Unsatisfied, Version};
+
+    fn register(store: &MemoryStore, key: &str, version: &str, scope: Scope) {
+        Provider {
+            key: key.to_string(),
+            version: Version::try_from(version).unwrap(),
+            ..Default::default()
+        }
+        .register(store, scope)
+        .unwrap();
+    }
+
+    #[test]
+    fn check_all() {
+        let store = MemoryStore::new();
+        register(&store, "runtime", "2.1", Scope::Machine);
+        register(&store, "tools", "1.0", Scope::User);
+
+        let requirements: Requirements = [
+            Requirement::new("runtime", "[2.0,)".parse().unwrap(), Scope::Machine),
+            Requirement::new("runtime", "[3.0,)".parse().unwrap(), Scope::Machine),
+            Requirement::new("tools", Default::default(), Scope::User),
+            Requirement::new("missing", Default::default(), Scope::User),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(requirements.len(), 4);
+
+        let report = requirements.check_all(&store).unwrap();
+        assert!(!report.is_satisfied());
+        assert_eq!(report.satisfied.len(), 2);
+        assert_eq!(report.unsatisfied.len(), 2);
+        assert_eq!(
+            report.unsatisfied[0].reason,
+            Some(Unsatisfied::BelowMinimum)
+        );
+        assert_eq!(
+            report.unsatisfied[1].reason,
+            Some(Unsatisfied::MissingProvider)
+        );
+        assert_eq!(
+            report.to_string(),
+            "runtime 2.1.0.0 is installed but 3.0.0.0 or later is required\nmissing is not installed\n"
+        );
+    }
+
+    #[test]
+    fn check_all_missing_root() {
+        let store = MemoryStore::new();
+        let mut requirements = Requirements::new();
+        requirements.push(Requirement::new(
+            "runtime",
+            Default::default(),
+            Scope::Machine,
+        ));
+
+        let report = requirements.check_all(&store).unwrap();
+        assert_eq!(
+            report.unsatisfied[0].reason,
+            Some(Unsatisfied::MissingProvider)
+        );
+    }
+
+    #[test]
+    fn check_all_empty() {
+        let report = Requirements::new().check_all(&MemoryStore::new()).unwrap();
+        assert!(report.is_satisfied());
+    }
+}
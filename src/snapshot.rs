@@ -0,0 +1,503 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+use crate::store::{DependencyStore, StoreKey};
+use crate::{Dependency, Error, Provider, Result, Scope, Version};
+
+/// The providers and dependents registered in one or more scopes at a point in time.
+///
+/// Compare two snapshots with [`Snapshot::diff`] e.g., before and after installing a package.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    entries: Vec<SnapshotEntry>,
+}
+
+/// A provider key and its dependents captured in a [`Snapshot`].
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotEntry {
+    /// Scope in which the provider key is registered.
+    pub scope: Scope,
+
+    /// Provider key.
+    pub key: String,
+
+    /// Provider information, or `None` if it could not be read.
+    pub provider: Option<Provider>,
+
+    /// Dependents registered for the provider.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dependents: Vec<Dependency>,
+}
+
+impl Snapshot {
+    /// Captures all providers and their dependents registered in each of the `scopes`.
+    pub fn capture<S>(store: &S, scopes: &[Scope]) -> Result<Self>
+    where
+        S: DependencyStore,
+    {
+        let mut entries = Vec::new();
+        for &scope in BTreeSet::from_iter(scopes.iter().copied()).iter() {
+            let Some(root) = crate::open_root(store, scope)? else {
+                continue;
+            };
+
+            for name in root.keys()? {
+                let key = match root.open_subkey(&name) {
+                    Err(Error::NotFound) => continue,
+                    err => err,
+                }?;
+
+                entries.push(SnapshotEntry {
+                    scope,
+                    provider: Provider::from(&name, &key).ok(),
+                    dependents: crate::read_dependents(&key)?.unwrap_or_default(),
+                    key: name,
+                });
+            }
+        }
+
+        Ok(Snapshot::from_iter(entries))
+    }
+
+    /// Gets all captured entries ordered by scope and key.
+    pub fn entries(&self) -> &[SnapshotEntry] {
+        &self.entries
+    }
+
+    /// Gets the entry for a provider key in a scope.
+    pub fn get(&self, scope: Scope, key: &str) -> Option<&SnapshotEntry> {
+        let key = key.to_uppercase();
+        self.entries
+            .iter()
+            .find(|e| e.scope == scope && e.key.to_uppercase() == key)
+    }
+
+    /// Gets the changes from this snapshot to a later snapshot.
+    ///
+    /// Dependents of added or removed providers are reported as added or removed as well.
+    pub fn diff(&self, after: &Snapshot) -> Diff {
+        let before = index(self);
+        let after = index(after);
+        let mut changes = Vec::new();
+
+        for id in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+            let (scope, _) = *id;
+            let before = before.get(id).copied();
+            let after = after.get(id).copied();
+
+            match (before, after) {
+                (None, Some(entry)) => changes.push(Change::ProviderAdded {
+                    scope,
+                    key: entry.key.clone(),
+                    version: entry.provider.as_ref().map(|p| p.version),
+                }),
+                (Some(entry), None) => changes.push(Change::ProviderRemoved {
+                    scope,
+                    key: entry.key.clone(),
+                    version: entry.provider.as_ref().map(|p| p.version),
+                }),
+                (Some(before), Some(after)) => {
+                    let before_version = before.provider.as_ref().map(|p| p.version);
+                    let after_version = after.provider.as_ref().map(|p| p.version);
+                    if before_version != after_version {
+                        changes.push(Change::VersionChanged {
+                            scope,
+                            key: after.key.clone(),
+                            before: before_version,
+                            after: after_version,
+                        });
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+
+            let provider_key = after.or(before).map(|e| e.key.as_str()).unwrap_or_default();
+            diff_dependents(
+                scope,
+                provider_key,
+                before.map(|e| e.dependents.as_slice()).unwrap_or_default(),
+                after.map(|e| e.dependents.as_slice()).unwrap_or_default(),
+                &mut changes,
+            );
+        }
+
+        Diff(changes)
+    }
+}
+
+impl FromIterator<SnapshotEntry> for Snapshot {
+    fn from_iter<T: IntoIterator<Item = SnapshotEntry>>(iter: T) -> Self {
+        let mut entries: Vec<_> = iter.into_iter().collect();
+        entries.sort_by_cached_key(|e| (e.scope, e.key.to_uppercase()));
+        Snapshot { entries }
+    }
+}
+
+/// A change between two [`Snapshot`] values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A provider key was registered.
+    ProviderAdded {
+        scope: Scope,
+        key: String,
+        version: Option<Version>,
+    },
+
+    /// A provider key was removed.
+    ProviderRemoved {
+        scope: Scope,
+        key: String,
+        version: Option<Version>,
+    },
+
+    /// The version of a provider changed, or could no longer be read.
+    VersionChanged {
+        scope: Scope,
+        key: String,
+        before: Option<Version>,
+        after: Option<Version>,
+    },
+
+    /// A dependent was registered for a provider.
+    DependentAdded {
+        scope: Scope,
+        provider_key: String,
+        dependent: Dependency,
+    },
+
+    /// A dependent was removed from a provider.
+    DependentRemoved {
+        scope: Scope,
+        provider_key: String,
+        dependent: Dependency,
+    },
+
+    /// The version range or attributes of a dependent changed.
+    DependentChanged {
+        scope: Scope,
+        provider_key: String,
+        before: Dependency,
+        after: Dependency,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn version(version: &Option<Version>) -> String {
+            version.map_or_else(|| "(unknown)".to_string(), |v| v.to_string())
+        }
+
+        match self {
+            Change::ProviderAdded {
+                scope,
+                key,
+                version: v,
+            } => write!(f, "{}: added provider {} {}", scope, key, version(v)),
+            Change::ProviderRemoved {
+                scope,
+                key,
+                version: v,
+            } => write!(f, "{}: removed provider {} {}", scope, key, version(v)),
+            Change::VersionChanged {
+                scope,
+                key,
+                before,
+                after,
+            } => write!(
+                f,
+                "{}: changed provider {} from {} to {}",
+                scope,
+                key,
+                version(before),
+                version(after)
+            ),
+            Change::DependentAdded {
+                scope,
+                provider_key,
+                dependent,
+            } => write!(
+                f,
+                "{}: added dependent {} to {} {}",
+                scope,
+                dependent.key,
+                provider_key,
+                dependent.range()
+            ),
+            Change::DependentRemoved {
+                scope,
+                provider_key,
+                dependent,
+            } => write!(
+                f,
+                "{}: removed dependent {} from {}",
+                scope, dependent.key, provider_key
+            ),
+            Change::DependentChanged {
+                scope,
+                provider_key,
+                before,
+                after,
+            } => write!(
+                f,
+                "{}: changed dependent {} of {} from {} to {}",
+                scope,
+                after.key,
+                provider_key,
+                before.range(),
+                after.range()
+            ),
+        }
+    }
+}
+
+/// The changes between two [`Snapshot`] values.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diff(Vec<Change>);
+
+impl Diff {
+    /// Gets whether there are no changes.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the number of changes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Gets an iterator over the changes.
+    pub fn iter(&self) -> std::slice::Iter<'_, Change> {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Diff {
+    type Item = Change;
+    type IntoIter = std::vec::IntoIter<Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Diff {
+    type Item = &'a Change;
+    type IntoIter = std::slice::Iter<'a, Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Display for Diff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for change in &self.0 {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+fn index(snapshot: &Snapshot) -> BTreeMap<(Scope, String), &SnapshotEntry> {
+    snapshot
+        .entries
+        .iter()
+        .map(|e| ((e.scope, e.key.to_uppercase()), e))
+        .collect()
+}
+
+fn diff_dependents(
+    scope: Scope,
+    provider_key: &str,
+    before: &[Dependency],
+    after: &[Dependency],
+    changes: &mut Vec<Change>,
+) {
+    let before: BTreeMap<_, _> = before.iter().map(|d| (d.key.to_uppercase(), d)).collect();
+    let after: BTreeMap<_, _> = after.iter().map(|d| (d.key.to_uppercase(), d)).collect();
+
+    for key in before.keys().chain(after.keys()).collect::<BTreeSet<_>>() {
+        let change = match (before.get(key), after.get(key)) {
+            (None, Some(&dependent)) => Change::DependentAdded {
+                scope,
+                provider_key: provider_key.to_string(),
+                dependent: dependent.clone(),
+            },
+            (Some(&dependent), None) => Change::DependentRemoved {
+                scope,
+                provider_key: provider_key.to_string(),
+                dependent: dependent.clone(),
+            },
+            (Some(&before), Some(&after))
+                if before.min_version != after.min_version
+                    || before.max_version != after.max_version
+                    || before.attributes != after.attributes =>
+            {
+                Change::DependentChanged {
+                    scope,
+                    provider_key: provider_key.to_string(),
+                    before: before.clone(),
+                    after: after.clone(),
+                }
+            }
+            _ => continue,
+        };
+        changes.push(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{register_dependent, unregister_dependent, MemoryStore};
+
+    fn register(store: &MemoryStore, key: &str, version: &str, scope: Scope) {
+        Provider {
+            key: key.to_string(),
+            version: Version::try_from(version).unwrap(),
+            ..Default::default()
+        }
+        .register(store, scope)
+        .unwrap();
+    }
+
+    #[test]
+    fn capture() {
+        let store = MemoryStore::new();
+        register(&store, "runtime", "1.0", Scope::Machine);
+        register(&store, "tools", "2.0", Scope::User);
+        register_dependent(
+            &store,
+            "runtime",
+            "bundle",
+            Scope::Machine,
+            Some(Version::from([1, 0, 0, 0])),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let snapshot = Snapshot::capture(&store, &[Scope::Machine, Scope::User]).unwrap();
+        let keys: Vec<_> = snapshot.entries().iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["tools", "runtime"]);
+
+        let entry = snapshot.get(Scope::Machine, "RUNTIME").unwrap();
+        assert_eq!(
+            entry.provider.as_ref().map(|p| p.version),
+            Some(Version::from([1, 0, 0, 0]))
+        );
+        assert_eq!(entry.dependents, vec![Dependency::new("bundle")]);
+        assert!(snapshot.get(Scope::User, "runtime").is_none());
+
+        let snapshot = Snapshot::capture(&store, &[Scope::User]).unwrap();
+        assert_eq!(snapshot.entries().len(), 1);
+    }
+
+    #[test]
+    fn capture_missing_root() {
+        let snapshot = Snapshot::capture(&MemoryStore::new(), &[Scope::Machine]).unwrap();
+        assert!(snapshot.entries().is_empty());
+    }
+
+    #[test]
+    fn diff() {
+        let store = MemoryStore::new();
+        register(&store, "runtime", "1.0", Scope::Machine);
+        register(&store, "old", "1.0", Scope::Machine);
+        register_dependent(&store, "runtime", "app", Scope::Machine, None, None, None).unwrap();
+        register_dependent(&store, "runtime", "tool", Scope::Machine, None, None, None).unwrap();
+        let before = Snapshot::capture(&store, &[Scope::Machine]).unwrap();
+        assert!(before.diff(&before).is_empty());
+
+        register(&store, "runtime", "1.1", Scope::Machine);
+        register(&store, "new", "2.0", Scope::Machine);
+        store
+            .create(Scope::Machine)
+            .unwrap()
+            .delete_subkey("old")
+            .unwrap();
+        unregister_dependent(&store, "runtime", "tool", Scope::Machine).unwrap();
+        register_dependent(
+            &store,
+            "runtime",
+            "app",
+            Scope::Machine,
+            Some(Version::from([1, 1, 0, 0])),
+            None,
+            None,
+        )
+        .unwrap();
+        register_dependent(&store, "new", "bundle", Scope::Machine, None, None, None).unwrap();
+        let after = Snapshot::capture(&store, &[Scope::Machine]).unwrap();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.len(), 6);
+        assert_eq!(
+            diff.to_string(),
+            "machine: added provider new 2.0.0.0
+machine: added dependent bundle to new (,)
+machine: removed provider old 1.0.0.0
+machine: changed provider runtime from 1.0.0.0 to 1.1.0.0
+machine: changed dependent app of runtime from (,) to (1.1.0.0,)
+machine: removed dependent tool from runtime
+"
+        );
+        assert!(matches!(
+            diff.iter().next(),
+            Some(Change::ProviderAdded { scope: Scope::Machine, key, .. }) if key == "new"
+        ));
+    }
+
+    #[test]
+    fn diff_scopes() {
+        let before = Snapshot::from_iter([SnapshotEntry {
+            scope: Scope::User,
+            key: "runtime".to_string(),
+            ..Default::default()
+        }]);
+        let after = Snapshot::from_iter([SnapshotEntry {
+            scope: Scope::Machine,
+            key: "Runtime".to_string(),
+            ..Default::default()
+        }]);
+
+        let changes: Vec<_> = before.diff(&after).into_iter().collect();
+        assert_eq!(
+            changes,
+            vec![
+                Change::ProviderRemoved {
+                    scope: Scope::User,
+                    key: "runtime".to_string(),
+                    version: None,
+                },
+                Change::ProviderAdded {
+                    scope: Scope::Machine,
+                    key: "Runtime".to_string(),
+                    version: None,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_serde() {
+        let store = MemoryStore::new();
+        register(&store, "runtime", "1.0", Scope::Machine);
+        let snapshot = Snapshot::capture(&store, &[Scope::Machine]).unwrap();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            r#"{"entries":[{"scope":"machine","key":"runtime","provider":{"key":"runtime","name":"","version":"1.0.0.0"},"dependents":[]}]}"#
+        );
+
+        let actual: Snapshot = serde_json::from_str(&json).unwrap();
+        assert!(snapshot.diff(&actual).is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::fmt::Display;
+
+use crate::store::{DependencyStore, StoreKey};
+use crate::{Dependency, Error, Result, Scope, UninstallEntry};
+
+const SCOPES: [Scope; 2] = [Scope::User, Scope::Machine];
+
+/// A dependent registered for a provider though the dependent itself is no longer installed.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StaleDependent {
+    /// The scope in which the dependent is registered.
+    pub scope: Scope,
+
+    /// The provider key under which the dependent is registered.
+    pub provider_key: String,
+
+    /// The stale dependent.
+    pub dependent: Dependency,
+}
+
+impl Display for StaleDependent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: stale dependent {} of {}",
+            self.scope, self.dependent.key, self.provider_key
+        )
+    }
+}
+
+/// Finds dependents registered in a scope for which no provider key exists.
+///
+/// Dependents are registered under their own provider key, which is looked up in both scopes since per-user packages
+/// often depend on per-machine packages and vice versa. Only the current user's scope is visible, so dependents of
+/// another user's per-user packages also appear stale. If `uninstalled` is true, dependents must also have no
+/// Add/Remove Programs entry for their key in either scope, and [`Error::NotSupported`] is returned if the store does not
+/// contain product registrations.
+pub fn find_stale_dependents<S>(
+    store: &S,
+    scope: Scope,
+    uninstalled: bool,
+) -> Result<Vec<StaleDependent>>
+where
+    S: DependencyStore,
+{
+    let Some(root) = crate::open_root(store, scope)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut stale = Vec::new();
+    for provider_key in root.keys()? {
+        let key = match root.open_subkey(&provider_key) {
+            Err(Error::NotFound) => continue,
+            err => err,
+        }?;
+
+        for dependent in crate::read_dependents(&key)?.unwrap_or_default() {
+            if is_installed(store, &dependent.key, uninstalled)? {
+                continue;
+            }
+
+            stale.push(StaleDependent {
+                scope,
+                provider_key: provider_key.clone(),
+                dependent,
+            });
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Stale dependents unregistered by [`prune`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pruned {
+    /// Stale dependents that were unregistered, or would have been for a dry run.
+    pub removed: Vec<StaleDependent>,
+
+    /// Stale dependents that could not be unregistered and the reason.
+    pub failed: Vec<(StaleDependent, Error)>,
+}
+
+/// Unregisters stale dependents found by [`find_stale_dependents`].
+///
+/// Since dependents of another user's per-user packages also appear stale in [`Scope::Machine`], pruning that scope
+/// returns [`Error::NotSupported`] unless `all_users` is true. If `dry_run` is true, nothing is unregistered and the
+/// dependents that would have been are returned. A dependent that cannot be unregistered does not stop the rest from
+/// being unregistered.
+pub fn prune<S>(
+    store: &S,
+    scope: Scope,
+    uninstalled: bool,
+    all_users: bool,
+    dry_run: bool,
+) -> Result<Pruned>
+where
+    S: DependencyStore,
+{
+    if scope == Scope::Machine && !all_users {
+        return Err(Error::NotSupported);
+    }
+
+    let stale = find_stale_dependents(store, scope, uninstalled)?;
+    if dry_run {
+        return Ok(Pruned {
+            removed: stale,
+            failed: Vec::new(),
+        });
+    }
+
+    let mut pruned = Pruned::default();
+    for s in stale {
+        match crate::unregister_dependent(store, &s.provider_key, &s.dependent.key, s.scope) {
+            Ok(()) => pruned.removed.push(s),
+            Err(err) => pruned.failed.push((s, err)),
+        }
+    }
+
+    Ok(pruned)
+}
+
+fn is_installed<S: DependencyStore>(store: &S, key: &str, uninstalled: bool) -> Result<bool> {
+    // A provider key is registered even if it cannot be read as a provider.
+    for scope in SCOPES {
+        let Some(root) = crate::open_root(store, scope)? else {
+            continue;
+        };
+        match root.open_subkey(key) {
+            Ok(_) => return Ok(true),
+            Err(Error::NotFound) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    if uninstalled {
+        for scope in SCOPES {
+            if UninstallEntry::get(store, key, scope)?.is_some() {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Data;
+    use crate::{check_dependents, register_dependent, MemoryKey, MemoryStore, Provider, Version};
+
+    fn store() -> MemoryStore {
+        let store = MemoryStore::new();
+        for (key, scope) in [("runtime", Scope::Machine), ("app", Scope::User)] {
+            Provider {
+                key: key.to_string(),
+                version: Version::from([1, 0, 0, 0]),
+                ..Default::default()
+            }
+            .register(&store, scope)
+            .unwrap();
+        }
+
+        for dependent in ["app", "crashed", "bundle"] {
+            register_dependent(
+                &store,
+                "runtime",
+                dependent,
+                Scope::Machine,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+        }
+        store
+            .create_uninstall(Scope::User)
+            .unwrap()
+            .create_subkey("bundle")
+            .unwrap();
+
+        store
+    }
+
+    #[test]
+    fn find_stale() {
+        let store = store();
+        let stale = find_stale_dependents(&store, Scope::Machine, false).unwrap();
+        let keys: Vec<_> = stale.iter().map(|s| s.dependent.key.as_str()).collect();
+        assert_eq!(keys, vec!["bundle", "crashed"]);
+        assert_eq!(
+            stale[1].to_string(),
+            "machine: stale dependent crashed of runtime"
+        );
+
+        let stale = find_stale_dependents(&store, Scope::Machine, true).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].dependent.key, "crashed");
+
+        assert!(find_stale_dependents(&store, Scope::User, false)
+            .unwrap()
+            .is_empty());
+        assert!(
+            find_stale_dependents(&MemoryStore::new(), Scope::User, false)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn find_stale_without_version() {
+        let store = store();
+        store
+            .create(Scope::User)
+            .unwrap()
+            .create_subkey("crashed")
+            .unwrap();
+        let stale = find_stale_dependents(&store, Scope::Machine, false).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].dependent.key, "bundle");
+    }
+
+    #[test]
+    fn prune_dry_run() {
+        let store = store();
+        let pruned = prune(&store, Scope::Machine, true, true, true).unwrap();
+        assert_eq!(pruned.removed.len(), 1);
+        assert_eq!(
+            check_dependents(&store, "runtime", Scope::Machine, None, None)
+                .unwrap()
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn prune_stale() {
+        let store = store();
+        let pruned = prune(&store, Scope::Machine, false, true, false).unwrap();
+        assert_eq!(pruned.removed.len(), 2);
+        assert!(pruned.failed.is_empty());
+
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].key, "app");
+        assert_eq!(
+            prune(&store, Scope::Machine, false, true, false).unwrap(),
+            Pruned::default()
+        );
+    }
+
+    #[test]
+    fn prune_machine_requires_all_users() {
+        let store = store();
+        assert_eq!(
+            prune(&store, Scope::Machine, false, false, true).unwrap_err(),
+            Error::NotSupported
+        );
+
+        register_dependent(&store, "app", "crashed", Scope::User, None, None, None).unwrap();
+        let pruned = prune(&store, Scope::User, false, false, false).unwrap();
+        assert_eq!(pruned.removed.len(), 1);
+        assert_eq!(pruned.removed[0].scope, Scope::User);
+    }
+
+    #[test]
+    fn prune_partial() {
+        let store = Failing(store(), "bundle");
+        let pruned = prune(&store, Scope::Machine, false, true, false).unwrap();
+        assert_eq!(pruned.removed.len(), 1);
+        assert_eq!(pruned.removed[0].dependent.key, "crashed");
+        assert_eq!(pruned.failed.len(), 1);
+        assert_eq!(pruned.failed[0].0.dependent.key, "bundle");
+        assert_eq!(pruned.failed[0].1, Error::NotSupported);
+    }
+
+    // A store that fails to delete a subkey with the given name.
+    struct Failing(MemoryStore, &'static str);
+
+    #[derive(Clone, Debug)]
+    struct FailingKey(MemoryKey, &'static str);
+
+    impl DependencyStore for Failing {
+        type Key = FailingKey;
+
+        fn open(&self, scope: Scope) -> Result<FailingKey> {
+            Ok(FailingKey(self.0.open(scope)?, self.1))
+        }
+
+        fn create(&self, scope: Scope) -> Result<FailingKey> {
+            Ok(FailingKey(self.0.create(scope)?, self.1))
+        }
+
+        fn open_uninstall(&self, scope: Scope) -> Result<Vec<FailingKey>> {
+            Ok(self
+                .0
+                .open_uninstall(scope)?
+                .into_iter()
+                .map(|key| FailingKey(key, self.1))
+                .collect())
+        }
+    }
+
+    impl StoreKey for FailingKey {
+        fn name(&self) -> &str {
+            self.0.name()
+        }
+
+        fn open_subkey(&self, name: &str) -> Result<Self> {
+            Ok(FailingKey(self.0.open_subkey(name)?, self.1))
+        }
+
+        fn create_subkey(&self, name: &str) -> Result<Self> {
+            Ok(FailingKey(self.0.create_subkey(name)?, self.1))
+        }
+
+        fn delete_subkey(&self, name: &str) -> Result<()> {
+            if name == self.1 {
+                return Err(Error::NotSupported);
+            }
+            self.0.delete_subkey(name)
+        }
+
+        fn keys(&self) -> Result<Vec<String>> {
+            self.0.keys()
+        }
+
+        fn values(&self) -> Result<Vec<(Option<String>, Data)>> {
+            self.0.values()
+        }
+
+        fn value(&self, name: Option<&str>) -> Result<Data> {
+            self.0.value(name)
+        }
+
+        fn set_value(&self, name: Option<&str>, data: Data) -> Result<()> {
+            self.0.set_value(name, data)
+        }
+
+        fn delete_value(&self, name: Option<&str>) -> Result<()> {
+            self.0.delete_value(name)
+        }
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use crate::{Error, Result, Scope, Version};
+
+/// A hierarchical store of provider and dependent records e.g., the Windows registry.
+///
+/// Providers are stored as subkeys of the root key for a [`Scope`], and dependents of a provider
+/// are stored as subkeys of its `Dependents` subkey.
+pub trait DependencyStore {
+    /// The type of key opened or created within the store.
+    type Key: StoreKey;
+
+    /// Opens the root key under which providers are registered for the given scope.
+    ///
+    /// Returns [`Error::NotFound`] if the root key does not exist.
+    fn open(&self, scope: Scope) -> Result<Self::Key>;
+
+    /// Opens or creates the root key under which providers are registered for the given scope.
+    fn create(&self, scope: Scope) -> Result<Self::Key>;
+
+    /// Opens the keys under which products are registered with Add/Remove Programs for the given scope, equivalent to
+    /// `Software\Microsoft\Windows\CurrentVersion\Uninstall` in the 64-bit and then the 32-bit registry view.
+    ///
+    /// Only keys that exist are returned. Returns [`Error::NotSupported`] by default for stores that do not contain
+    /// product registrations.
+    fn open_uninstall(&self, scope: Scope) -> Result<Vec<Self::Key>> {
+        let _ = scope;
+        Err(Error::NotSupported)
+    }
+
+    /// Runs `f` with exclusive access to the given scope so that changes spanning several calls are atomic.
+    ///
+    /// By default `f` is simply run, as with the registry where only each call is atomic. Nested calls for the same
+    /// scope on the same thread must not block.
+    fn exclusive<T>(&self, scope: Scope, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let _ = scope;
+        f()
+    }
+}
+
+/// A key within a [`DependencyStore`] containing named values and subkeys.
+pub trait StoreKey: Sized {
+    /// Gets the name of the key.
+    fn name(&self) -> &str;
+
+    /// Opens an existing subkey.
+    ///
+    /// Returns [`Error::NotFound`] if the subkey does not exist.
+    fn open_subkey(&self, name: &str) -> Result<Self>;
+
+    /// Opens or creates a subkey.
+    fn create_subkey(&self, name: &str) -> Result<Self>;
+
+    /// Deletes a subkey along with all its values and subkeys.
+    ///
+    /// Returns [`Error::NotFound`] if the subkey does not exist.
+    fn delete_subkey(&self, name: &str) -> Result<()>;
+
+    /// Gets the names of all subkeys.
+    fn keys(&self) -> Result<Vec<String>>;
+
+    /// Gets the names and data of all values. The default value, if set, has no name.
+    fn values(&self) -> Result<Vec<(Option<String>, Data)>>;
+
+    /// Gets the data of a value, or the default value if `name` is `None`.
+    ///
+    /// Returns [`Error::NotFound`] if the value does not exist.
+    fn value(&self, name: Option<&str>) -> Result<Data>;
+
+    /// Sets the data of a value, or the default value if `name` is `None`.
+    fn set_value(&self, name: Option<&str>, data: Data) -> Result<()>;
+
+    /// Deletes a value, or the default value if `name` is `None`.
+    ///
+    /// Returns [`Error::NotFound`] if the value does not exist.
+    fn delete_value(&self, name: Option<&str>) -> Result<()>;
+}
+
+/// Data stored in a value of a [`StoreKey`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Data {
+    Binary(Vec<u8>),
+    DWord(u32),
+    MultiString(Vec<String>),
+    QWord(u64),
+    String(String),
+}
+
+impl Data {
+    pub(crate) fn to_string(&self) -> Result<String> {
+        if let Data::String(s) = self {
+            return Ok(s.clone());
+        }
+
+        Err(Error::Format)
+    }
+
+    pub(crate) fn to_version(&self) -> Result<Version> {
+        match self {
+            Data::String(s) => Version::try_from(s.as_str()),
+            Data::QWord(d) => Ok(Version::from(*d)),
+            _ => Err(Error::Format),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_to_string() {
+        assert_eq!(
+            Data::String("test".to_string()).to_string().unwrap(),
+            "test"
+        );
+        assert_eq!(Data::DWord(1).to_string().unwrap_err(), Error::Format);
+    }
+
+    #[test]
+    fn data_to_version() {
+        assert_eq!(
+            Data::String("1.2.3.4".to_string()).to_version().unwrap(),
+            Version::from([1, 2, 3, 4])
+        );
+        assert_eq!(
+            Data::QWord(281483566841860u64).to_version().unwrap(),
+            Version::from([1, 2, 3, 4])
+        );
+        assert_eq!(Data::DWord(1).to_version().unwrap_err(), Error::Format);
+    }
+}
@@ -0,0 +1,316 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::{Attributes, Error, Provider, Result, Scope};
+
+/// A product registered with Add/Remove Programs under `Software\Microsoft\Windows\CurrentVersion\Uninstall`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UninstallEntry {
+    /// Name of the key e.g., a ProductCode for a Windows Installer package or bundle code for a Burn bundle.
+    pub id: String,
+
+    /// The `DisplayName` value.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub display_name: Option<String>,
+
+    /// The `DisplayVersion` value.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub display_version: Option<String>,
+
+    /// The `Publisher` value.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub publisher: Option<String>,
+
+    /// The `UninstallString` value.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub uninstall_string: Option<String>,
+
+    /// The `InstallDate` value, typically formatted as `YYYYMMDD`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub install_date: Option<String>,
+}
+
+impl UninstallEntry {
+    /// Gets the Add/Remove Programs entry for a product, or `None` if it is not registered.
+    ///
+    /// Both the 64-bit and 32-bit registry views are searched. Per-user Burn bundles are registered with Add/Remove
+    /// Programs in [`Scope::User`], but per-user Windows Installer products are registered per machine, so are not
+    /// found in that scope. Returns [`Error::NotSupported`]
+    /// if the store does not contain product registrations.
+    pub fn get<S, K>(store: &S, id: K, scope: Scope) -> Result<Option<Self>>
+    where
+        S: DependencyStore,
+        K: AsRef<str>,
+    {
+        for root in store.open_uninstall(scope)? {
+            match root.open_subkey(id.as_ref()) {
+                Err(Error::NotFound) => continue,
+                key => return UninstallEntry::read(&key?).map(Some),
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read(key: &impl StoreKey) -> Result<Self> {
+        // Values of an unexpected type are as good as missing for display purposes.
+        let value = |name| match key.value(Some(name)) {
+            Ok(Data::String(s)) => Ok(Some(s)),
+            Ok(_) | Err(Error::NotFound) => Ok(None),
+            Err(err) => Err(err),
+        };
+
+        Ok(UninstallEntry {
+            id: key.name().to_string(),
+            display_name: value("DisplayName")?,
+            display_version: value("DisplayVersion")?,
+            publisher: value("Publisher")?,
+            uninstall_string: value("UninstallString")?,
+            install_date: value("InstallDate")?,
+        })
+    }
+}
+
+/// A provider joined to the Add/Remove Programs entry of the product that registered it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProviderOwner {
+    /// The scope in which the provider and product are registered.
+    pub scope: Scope,
+
+    /// The provider.
+    pub provider: Provider,
+
+    /// The entry for the [`Provider::id`], if the provider has an identifier and the product is registered.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub entry: Option<UninstallEntry>,
+}
+
+impl ProviderOwner {
+    /// Gets a provider and the Add/Remove Programs entry of the product that owns it.
+    pub fn get<S, K>(store: &S, provider_key: K, scope: Scope) -> Result<Self>
+    where
+        S: DependencyStore,
+        K: AsRef<str> + Into<String>,
+    {
+        let provider = crate::get_provider(store, provider_key, scope)?;
+        ProviderOwner::join(store, provider, scope)
+    }
+
+    /// Gets all providers registered in a scope and the Add/Remove Programs entries of the products that own them.
+    pub fn all<S>(store: &S, scope: Scope) -> Result<Vec<Self>>
+    where
+        S: DependencyStore,
+    {
+        crate::providers(store, scope)?
+            .map(|provider| ProviderOwner::join(store, provider?, scope))
+            .collect()
+    }
+
+    /// Whether the provider identifies a product that is no longer registered with Add/Remove Programs.
+    ///
+    /// Providers without an identifier cannot be correlated and are never orphaned. In [`Scope::User`], only providers
+    /// registered by a bundle with [`Attributes::BUNDLE`] can be orphaned, since per-user Burn bundles are registered
+    /// with Add/Remove Programs in that scope but per-user Windows Installer products are not.
+    pub fn is_orphaned(&self) -> bool {
+        let correlated = match self.scope {
+            Scope::Machine => true,
+            Scope::User => self
+                .provider
+                .attributes
+                .is_some_and(|attributes| attributes.contains(Attributes::BUNDLE)),
+        };
+        correlated && self.provider.id.is_some() && self.entry.is_none()
+    }
+
+    fn join<S: DependencyStore>(store: &S, provider: Provider, scope: Scope) -> Result<Self> {
+        let entry = match &provider.id {
+            Some(id) => UninstallEntry::get(store, id, scope)?,
+            None => None,
+        };
+
+        Ok(ProviderOwner {
+            scope,
+            provider,
+            entry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MemoryStore, Version};
+
+    fn store() -> MemoryStore {
+        let store = MemoryStore::new();
+        for (key, id) in [
+            ("runtime", Some("{F8E9F4B1-0000-0000-0000-000000000000}")),
+            ("removed", Some("{A0000000-0000-0000-0000-000000000000}")),
+            ("tools", None),
+        ] {
+            Provider {
+                key: key.to_string(),
+                version: Version::from([1, 0, 0, 0]),
+                id: id.map(str::to_string),
+                ..Default::default()
+            }
+            .register(&store, Scope::Machine)
+            .unwrap();
+        }
+
+        let product = store
+            .create_uninstall(Scope::Machine)
+            .unwrap()
+            .create_subkey("{F8E9F4B1-0000-0000-0000-000000000000}")
+            .unwrap();
+        for (name, value) in [
+            ("DisplayName", "Example Runtime"),
+            ("DisplayVersion", "1.0"),
+            ("Publisher", "Example"),
+            (
+                "UninstallString",
+                "MsiExec.exe /X{F8E9F4B1-0000-0000-0000-000000000000}",
+            ),
+            ("InstallDate", "20231115"),
+        ] {
+            product
+                .set_value(Some(name), Data::String(value.to_string()))
+                .unwrap();
+        }
+        product.set_value(Some("Version"), Data::DWord(1)).unwrap();
+
+        store
+    }
+
+    #[test]
+    fn get_entry() {
+        let store = store();
+        let entry = UninstallEntry::get(
+            &store,
+            "{f8e9f4b1-0000-0000-0000-000000000000}",
+            Scope::Machine,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(entry.id, "{F8E9F4B1-0000-0000-0000-000000000000}");
+        assert_eq!(entry.display_name.as_deref(), Some("Example Runtime"));
+        assert_eq!(entry.display_version.as_deref(), Some("1.0"));
+        assert_eq!(entry.publisher.as_deref(), Some("Example"));
+        assert_eq!(entry.install_date.as_deref(), Some("20231115"));
+
+        assert_eq!(
+            UninstallEntry::get(
+                &store,
+                "{F8E9F4B1-0000-0000-0000-000000000000}",
+                Scope::User
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn get_owner() {
+        let store = store();
+        let owner = ProviderOwner::get(&store, "runtime", Scope::Machine).unwrap();
+        assert_eq!(
+            owner.entry.unwrap().display_name.as_deref(),
+            Some("Example Runtime")
+        );
+
+        assert_eq!(
+            ProviderOwner::get(&store, "missing", Scope::Machine).unwrap_err(),
+            Error::NotFound
+        );
+    }
+
+    #[test]
+    fn all_owners() {
+        let owners = ProviderOwner::all(&store(), Scope::Machine).unwrap();
+        let orphaned: Vec<_> = owners
+            .iter()
+            .map(|o| (o.provider.key.as_str(), o.is_orphaned()))
+            .collect();
+        assert_eq!(
+            orphaned,
+            vec![("removed", true), ("runtime", false), ("tools", false)]
+        );
+        assert!(owners.iter().all(|o| o.scope == Scope::Machine));
+
+        // Per-user Windows Installer products cannot be correlated.
+        let store = store();
+        Provider {
+            key: "user".to_string(),
+            version: Version::from([1, 0, 0, 0]),
+            id: Some("{B0000000-0000-0000-0000-000000000000}".to_string()),
+            ..Default::default()
+        }
+        .register(&store, Scope::User)
+        .unwrap();
+        let owner = ProviderOwner::get(&store, "user", Scope::User).unwrap();
+        assert_eq!(owner.entry, None);
+        assert!(!owner.is_orphaned());
+
+        // Per-user bundles are registered in the same scope.
+        let bundle = |id: &str| {
+            Provider {
+                key: "bundle".to_string(),
+                version: Version::from([1, 0, 0, 0]),
+                id: Some(id.to_string()),
+                attributes: Some(Attributes::BUNDLE),
+                ..Default::default()
+            }
+            .register(&store, Scope::User)
+            .unwrap();
+            ProviderOwner::get(&store, "bundle", Scope::User).unwrap()
+        };
+        assert!(bundle("{C0000000-0000-0000-0000-000000000000}").is_orphaned());
+
+        store
+            .create_uninstall(Scope::User)
+            .unwrap()
+            .create_subkey("{D0000000-0000-0000-0000-000000000000}")
+            .unwrap();
+        let owner = bundle("{D0000000-0000-0000-0000-000000000000}");
+        assert_eq!(
+            owner.entry.map(|entry| entry.id).as_deref(),
+            Some("{D0000000-0000-0000-0000-000000000000}")
+        );
+    }
+
+    #[cfg(feature = "file")]
+    #[test]
+    fn not_supported() {
+        let store = crate::FileStore::new(std::env::temp_dir(), crate::Format::Json);
+        assert_eq!(
+            UninstallEntry::get(
+                &store,
+                "{F8E9F4B1-0000-0000-0000-000000000000}",
+                Scope::User
+            )
+            .unwrap_err(),
+            Error::NotSupported
+        );
+    }
+}
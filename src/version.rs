@@ -1,8 +1,8 @@
 // Copyright 2023 Heath Stewart.
 // Licensed under the MIT License. See LICENSE.txt in the project root for license information.
 
-use crate::Error;
-use std::fmt::Display;
+use crate::{Attributes, Error};
+use std::{fmt::Display, str::FromStr};
 
 /// A comparable version containing major.minor.build.revision fields.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -78,7 +78,7 @@ impl TryFrom<&str> for Version {
     type Error = crate::Error;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let value = value.trim_start_matches(|c| c == 'v' || c == 'V');
+        let value = value.trim_start_matches(['v', 'V']);
 
         let mut fields = [0u16; 4];
 
@@ -95,6 +95,219 @@ impl TryFrom<&str> for Version {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Version {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Version {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Version::try_from(value.as_str())
+            .map_err(|_| serde::de::Error::custom(format!("invalid version {:?}", value)))
+    }
+}
+
+/// A range of versions with optional inclusive or exclusive bounds.
+///
+/// As with WiX, bounds are exclusive unless [`Attributes::MIN_VERSION_INCLUSIVE`] or [`Attributes::MAX_VERSION_INCLUSIVE`]
+/// are specified. Ranges format as and parse from interval notation e.g., `[1.0,2.0)` where either bound may be omitted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VersionRange {
+    min: Option<Version>,
+    max: Option<Version>,
+    min_inclusive: bool,
+    max_inclusive: bool,
+}
+
+impl VersionRange {
+    /// Creates a range from optional bounds with inclusivity specified by `attributes`.
+    pub fn new(min: Option<Version>, max: Option<Version>, attributes: Attributes) -> Self {
+        VersionRange {
+            min,
+            max,
+            min_inclusive: min.is_some() && attributes.contains(Attributes::MIN_VERSION_INCLUSIVE),
+            max_inclusive: max.is_some() && attributes.contains(Attributes::MAX_VERSION_INCLUSIVE),
+        }
+    }
+
+    /// Gets the minimum version, if bounded.
+    pub fn min(&self) -> Option<Version> {
+        self.min
+    }
+
+    /// Gets the maximum version, if bounded.
+    pub fn max(&self) -> Option<Version> {
+        self.max
+    }
+
+    /// Gets whether the minimum version is included in the range.
+    pub fn min_inclusive(&self) -> bool {
+        self.min_inclusive
+    }
+
+    /// Gets whether the maximum version is included in the range.
+    pub fn max_inclusive(&self) -> bool {
+        self.max_inclusive
+    }
+
+    /// Gets whether neither bound is specified.
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Gets the attributes describing the inclusivity of the bounds.
+    pub fn attributes(&self) -> Attributes {
+        let mut attributes = Attributes::NONE;
+        if self.min_inclusive {
+            attributes |= Attributes::MIN_VERSION_INCLUSIVE;
+        }
+        if self.max_inclusive {
+            attributes |= Attributes::MAX_VERSION_INCLUSIVE;
+        }
+        attributes
+    }
+
+    /// Gets whether the `version` is within the range.
+    pub fn contains(&self, version: Version) -> bool {
+        // Equivalent to the range checks in deputil:DepCheckDependency.
+        if let Some(min) = self.min {
+            if !(self.min_inclusive && min <= version || min < version) {
+                return false;
+            }
+        }
+
+        if let Some(max) = self.max {
+            if !(self.max_inclusive && version <= max || version < max) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Gets whether no version is within the range.
+    pub fn is_empty(&self) -> bool {
+        // Versions are discrete, so compare the smallest and largest contained versions.
+        let lowest = match self.min {
+            Some(min) if self.min_inclusive => Some(u64::from(min)),
+            Some(min) => u64::from(min).checked_add(1),
+            None => Some(u64::MIN),
+        };
+        let highest = match self.max {
+            Some(max) if self.max_inclusive => Some(u64::from(max)),
+            Some(max) => u64::from(max).checked_sub(1),
+            None => Some(u64::MAX),
+        };
+
+        match (lowest, highest) {
+            (Some(lowest), Some(highest)) => lowest > highest,
+            _ => true,
+        }
+    }
+
+    /// Gets the range of versions contained in both `self` and `other`.
+    pub fn intersection(&self, other: &VersionRange) -> VersionRange {
+        let (min, min_inclusive) = match (self.min, other.min) {
+            (Some(a), Some(b)) if a == b => (Some(a), self.min_inclusive && other.min_inclusive),
+            (Some(a), Some(b)) if a > b => (Some(a), self.min_inclusive),
+            (Some(_), Some(b)) => (Some(b), other.min_inclusive),
+            (Some(a), None) => (Some(a), self.min_inclusive),
+            (None, b) => (b, other.min_inclusive),
+        };
+        let (max, max_inclusive) = match (self.max, other.max) {
+            (Some(a), Some(b)) if a == b => (Some(a), self.max_inclusive && other.max_inclusive),
+            (Some(a), Some(b)) if a < b => (Some(a), self.max_inclusive),
+            (Some(_), Some(b)) => (Some(b), other.max_inclusive),
+            (Some(a), None) => (Some(a), self.max_inclusive),
+            (None, b) => (b, other.max_inclusive),
+        };
+
+        VersionRange {
+            min,
+            max,
+            min_inclusive,
+            max_inclusive,
+        }
+    }
+}
+
+impl Display for VersionRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", if self.min_inclusive { '[' } else { '(' })?;
+        if let Some(min) = self.min {
+            write!(f, "{}", min)?;
+        }
+        write!(f, ",")?;
+        if let Some(max) = self.max {
+            write!(f, "{}", max)?;
+        }
+        write!(f, "{}", if self.max_inclusive { ']' } else { ')' })
+    }
+}
+
+impl FromStr for VersionRange {
+    type Err = Error;
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        let min_inclusive = match s.chars().next() {
+            Some('[') => true,
+            Some('(') => false,
+            _ => return Err(Error::Format),
+        };
+        let max_inclusive = match s.chars().last() {
+            Some(']') if s.len() > 1 => true,
+            Some(')') if s.len() > 1 => false,
+            _ => return Err(Error::Format),
+        };
+
+        let bound = |s: &str| -> std::result::Result<Option<Version>, Error> {
+            match s.trim() {
+                "" => Ok(None),
+                s => Version::try_from(s).map(Some),
+            }
+        };
+
+        let inner = &s[1..s.len() - 1];
+        let (min, max) = match inner.split_once(',') {
+            Some((min, max)) => (bound(min)?, bound(max)?),
+            // A single version e.g., [1.0] must be inclusive.
+            None if min_inclusive && max_inclusive => {
+                let version = bound(inner)?.ok_or(Error::Format)?;
+                (Some(version), Some(version))
+            }
+            None => return Err(Error::Format),
+        };
+
+        Ok(VersionRange {
+            min,
+            max,
+            min_inclusive: min_inclusive && min.is_some(),
+            max_inclusive: max_inclusive && max.is_some(),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for VersionRange {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for VersionRange {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+        value
+            .parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid version range {:?}", value)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,7 +327,8 @@ mod tests {
 
     #[test]
     fn version_into_u64() {
-        assert_eq!(281483566841860u64, Version::from([1, 2, 3, 4]).into());
+        let value: u64 = Version::from([1, 2, 3, 4]).into();
+        assert_eq!(281483566841860u64, value);
     }
 
     #[test]
@@ -215,4 +429,132 @@ mod tests {
         assert_eq!(version.build(), 3);
         assert_eq!(version.revision(), 4);
     }
+
+    fn v(s: &str) -> Version {
+        Version::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn version_range_contains() {
+        let range = VersionRange::new(Some(v("1.0")), Some(v("2.0")), Attributes::NONE);
+        assert!(!range.contains(v("1.0")));
+        assert!(range.contains(v("1.5")));
+        assert!(!range.contains(v("2.0")));
+
+        let range = VersionRange::new(
+            Some(v("1.0")),
+            Some(v("2.0")),
+            Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE,
+        );
+        assert!(range.contains(v("1.0")));
+        assert!(range.contains(v("2.0")));
+        assert!(!range.contains(v("2.0.0.1")));
+
+        assert!(VersionRange::default().contains(v("0.0")));
+    }
+
+    #[test]
+    fn version_range_attributes() {
+        let range = VersionRange::new(
+            None,
+            Some(v("2.0")),
+            Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE,
+        );
+        assert!(!range.min_inclusive());
+        assert_eq!(range.attributes(), Attributes::MAX_VERSION_INCLUSIVE);
+    }
+
+    #[test]
+    fn version_range_is_empty() {
+        assert!(!VersionRange::default().is_empty());
+        assert!(VersionRange::new(Some(v("2.0")), Some(v("1.0")), Attributes::NONE).is_empty());
+        assert!(VersionRange::new(
+            Some(v("1.0")),
+            Some(v("1.0")),
+            Attributes::MIN_VERSION_INCLUSIVE
+        )
+        .is_empty());
+        assert!(!"[1.0]".parse::<VersionRange>().unwrap().is_empty());
+        assert!(VersionRange::new(Some(v("1.0")), Some(v("1.0.0.1")), Attributes::NONE).is_empty());
+        assert!(
+            !VersionRange::new(Some(v("1.0")), Some(v("1.0.0.2")), Attributes::NONE).is_empty()
+        );
+        assert!(
+            VersionRange::new(Some(Version::from(u64::MAX)), None, Attributes::NONE).is_empty()
+        );
+    }
+
+    #[test]
+    fn version_range_intersection() {
+        let a: VersionRange = "[1.0,3.0)".parse().unwrap();
+        let b: VersionRange = "(2.0,3.0]".parse().unwrap();
+        assert_eq!(a.intersection(&b), "(2.0,3.0)".parse().unwrap());
+
+        let b: VersionRange = "[1.0,)".parse().unwrap();
+        assert_eq!(a.intersection(&b), a);
+        assert_eq!(VersionRange::default().intersection(&b), b);
+
+        let b: VersionRange = "[4.0,)".parse().unwrap();
+        assert!(a.intersection(&b).is_empty());
+    }
+
+    #[test]
+    fn version_range_to_string() {
+        assert_eq!(
+            "[1.0,2.0)".parse::<VersionRange>().unwrap().to_string(),
+            "[1.0.0.0,2.0.0.0)"
+        );
+        assert_eq!(VersionRange::default().to_string(), "(,)");
+        assert_eq!(
+            VersionRange::new(None, Some(v("2.0")), Attributes::MAX_VERSION_INCLUSIVE).to_string(),
+            "(,2.0.0.0]"
+        );
+    }
+
+    #[test]
+    fn version_range_from_str() {
+        assert_eq!(
+            " ( 1.0 , ] ".parse::<VersionRange>().unwrap(),
+            VersionRange::new(Some(v("1.0")), None, Attributes::NONE)
+        );
+        assert_eq!(
+            "[1.2.3.4]".parse::<VersionRange>().unwrap(),
+            VersionRange::new(
+                Some(v("1.2.3.4")),
+                Some(v("1.2.3.4")),
+                Attributes::MIN_VERSION_INCLUSIVE | Attributes::MAX_VERSION_INCLUSIVE
+            )
+        );
+        assert_eq!("1.0".parse::<VersionRange>().unwrap_err(), Error::Format);
+        assert_eq!("(1.0)".parse::<VersionRange>().unwrap_err(), Error::Format);
+        assert_eq!("[a,b]".parse::<VersionRange>().unwrap_err(), Error::Format);
+        assert_eq!("[".parse::<VersionRange>().unwrap_err(), Error::Format);
+        assert_eq!("[]".parse::<VersionRange>().unwrap_err(), Error::Format);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn version_serde() {
+        let version = Version::from([1, 2, 3, 4]);
+        assert_eq!(serde_json::to_string(&version).unwrap(), r#""1.2.3.4""#);
+        assert_eq!(
+            serde_json::from_str::<Version>(r#""v1.2.3.4""#).unwrap(),
+            version
+        );
+        assert!(serde_json::from_str::<Version>(r#""1.x""#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn version_range_serde() {
+        let range: VersionRange = "[1.0,2.0)".parse().unwrap();
+        assert_eq!(
+            serde_json::to_string(&range).unwrap(),
+            r#""[1.0.0.0,2.0.0.0)""#
+        );
+        assert_eq!(
+            serde_json::from_str::<VersionRange>(r#""[1.0,2.0)""#).unwrap(),
+            range
+        );
+    }
 }
@@ -0,0 +1,989 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::{
+    cell::RefCell,
+    fs::{self, File, OpenOptions},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::regfile;
+use crate::store::{Data, DependencyStore, StoreKey};
+use crate::{Error, Result, Scope};
+
+// cspell:ignore wineserver
+
+const HEADER: &str = "WINE REGISTRY Version 2";
+const ROOT_PATH: &str = r"Software\Classes\Installer\Dependencies";
+const UNINSTALL_PATHS: [&str; 2] = [
+    r"Software\Microsoft\Windows\CurrentVersion\Uninstall",
+    r"Software\Wow6432Node\Microsoft\Windows\CurrentVersion\Uninstall",
+];
+
+// Seconds between the Windows epoch of 1601-01-01 and the Unix epoch.
+const EPOCH_DIFFERENCE: u64 = 11_644_473_600;
+
+// Distinguishes temporary files written by threads of this process.
+static TEMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // Registry files loaded by the current thread within WineRegistry::exclusive.
+    static PENDING: RefCell<Vec<Pending>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A [`DependencyStore`] backed by the `user.reg` and `system.reg` files of a Wine prefix.
+///
+/// Files are locked using a separate `.lock` file and read for every operation, or only once for operations like
+/// [`register_dependent`](crate::register_dependent) that make several changes, which are then written together.
+/// Changes are written to a temporary file that replaces the original, preserving all content outside the changed
+/// keys. Stop the `wineserver` for the prefix before making changes, or it will overwrite them when it
+/// next saves the registry.
+#[derive(Clone, Debug)]
+pub struct WineRegistry {
+    prefix: PathBuf,
+}
+
+impl WineRegistry {
+    /// Creates a store for the Wine prefix directory e.g., `~/.wine`.
+    pub fn new(prefix: impl Into<PathBuf>) -> Self {
+        WineRegistry {
+            prefix: prefix.into(),
+        }
+    }
+
+    fn path(&self, scope: Scope) -> PathBuf {
+        let file = match scope {
+            Scope::User => "user.reg",
+            Scope::Machine => "system.reg",
+        };
+        self.prefix.join(file)
+    }
+
+    fn root(&self, scope: Scope, path: &str) -> WineKey {
+        WineKey {
+            file: self.path(scope),
+            path: path.to_string(),
+            name: path.rsplit('\\').next().unwrap_or_default().to_string(),
+        }
+    }
+
+    fn open_path(&self, scope: Scope, path: &str) -> Result<WineKey> {
+        let key = self.root(scope, path);
+        key.read(|_| Ok(()))?;
+        Ok(key)
+    }
+}
+
+impl DependencyStore for WineRegistry {
+    type Key = WineKey;
+
+    fn open(&self, scope: Scope) -> Result<WineKey> {
+        self.open_path(scope, ROOT_PATH)
+    }
+
+    fn create(&self, scope: Scope) -> Result<WineKey> {
+        let key = self.root(scope, ROOT_PATH);
+        access(&key.file, true, |file| {
+            let file = file.get_or_insert_with(|| RegistryFile::new(scope));
+            if file.exists(&key.path) {
+                return Ok(((), false));
+            }
+
+            file.insert(&key.path);
+            Ok(((), true))
+        })?;
+
+        Ok(key)
+    }
+
+    fn open_uninstall(&self, scope: Scope) -> Result<Vec<WineKey>> {
+        let mut keys = Vec::new();
+        for path in UNINSTALL_PATHS {
+            match self.open_path(scope, path) {
+                Err(Error::NotFound) => continue,
+                key => keys.push(key?),
+            }
+        }
+        Ok(keys)
+    }
+
+    fn exclusive<T>(&self, scope: Scope, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let path = self.path(scope);
+        if PENDING.with_borrow(|pending| pending.iter().any(|p| p.path == path)) {
+            return f();
+        }
+
+        let lock = lock(&path, true)?;
+        let file = RegistryFile::load(&path)?;
+        PENDING.with_borrow_mut(|pending| {
+            pending.push(Pending {
+                path: path.clone(),
+                file,
+                changed: false,
+                _lock: lock,
+            })
+        });
+
+        // Discard the file if `f` panics.
+        struct Guard<'a>(&'a Path);
+        impl Drop for Guard<'_> {
+            fn drop(&mut self) {
+                PENDING.with_borrow_mut(|pending| pending.retain(|p| p.path != self.0));
+            }
+        }
+
+        let guard = Guard(&path);
+        let result = f();
+        let pending = PENDING.with_borrow_mut(|pending| {
+            let index = pending.iter().position(|p| p.path == path)?;
+            Some(pending.remove(index))
+        });
+        drop(guard);
+
+        // Like other stores, changes made before an error are kept. The lock is held until the file is saved.
+        let saved = match pending {
+            Some(Pending {
+                file: Some(file),
+                changed: true,
+                ..
+            }) => file.save(&path),
+            _ => Ok(()),
+        };
+        result.and_then(|value| saved.map(|_| value))
+    }
+}
+
+// A registry file loaded within WineRegistry::exclusive and saved when it completes.
+struct Pending {
+    path: PathBuf,
+    file: Option<RegistryFile>,
+    changed: bool,
+    _lock: Option<File>,
+}
+
+// Locks a registry file using a separate file, since the registry file is replaced when saved. Readers only lock an
+// existing lock file and otherwise return `None`, so that a prefix that is read-only or owned by another user can still
+// be read; since files are replaced when saved, readers never see partial content.
+fn lock(path: &Path, exclusive: bool) -> Result<Option<File>> {
+    let mut lock = path.as_os_str().to_owned();
+    lock.push(".lock");
+
+    if !exclusive {
+        return match File::open(lock) {
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    ErrorKind::NotFound | ErrorKind::PermissionDenied
+                ) =>
+            {
+                Ok(None)
+            }
+            file => {
+                let file = file?;
+                file.lock_shared()?;
+                Ok(Some(file))
+            }
+        };
+    }
+
+    let file = match OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock)
+    {
+        Err(err) if err.kind() == ErrorKind::NotFound => return Err(Error::NotFound),
+        file => file?,
+    };
+    file.lock()?;
+    Ok(Some(file))
+}
+
+// Runs `f` on a registry file, or `None` if it does not exist, and saves the file if `f` returns that it changed it.
+// Within WineRegistry::exclusive the loaded file is used and saved once that completes.
+fn access<T>(
+    path: &Path,
+    exclusive: bool,
+    f: impl FnOnce(&mut Option<RegistryFile>) -> Result<(T, bool)>,
+) -> Result<T> {
+    let pending = PENDING.with_borrow_mut(|pending| {
+        let p = pending.iter_mut().find(|p| p.path == path)?;
+        Some(p.file.take())
+    });
+
+    if let Some(mut file) = pending {
+        let result = f(&mut file);
+        PENDING.with_borrow_mut(|pending| {
+            if let Some(p) = pending.iter_mut().find(|p| p.path == path) {
+                p.file = file;
+                p.changed |= matches!(result, Ok((_, true)));
+            }
+        });
+        return result.map(|(value, _)| value);
+    }
+
+    let _lock = lock(path, exclusive)?;
+    let mut file = RegistryFile::load(path)?;
+    let (value, changed) = f(&mut file)?;
+    if let (Some(file), true) = (&file, changed) {
+        file.save(path)?;
+    }
+    Ok(value)
+}
+
+/// A key within a [`WineRegistry`].
+///
+/// The key refers to a path within a registry file, so operations on a key that was since deleted return
+/// [`Error::NotFound`].
+#[derive(Clone, Debug)]
+pub struct WineKey {
+    file: PathBuf,
+    path: String,
+    name: String,
+}
+
+impl WineKey {
+    fn read<T>(&self, f: impl FnOnce(&RegistryFile) -> Result<T>) -> Result<T> {
+        access(&self.file, false, |file| {
+            let file = file.as_ref().ok_or(Error::NotFound)?;
+            self.find(file)?;
+            Ok((f(file)?, false))
+        })
+    }
+
+    // Runs `f` on the file if the key exists, saving the file if `f` returns that it changed it.
+    fn write<T>(&self, f: impl FnOnce(&mut RegistryFile) -> Result<(T, bool)>) -> Result<T> {
+        access(&self.file, true, |file| {
+            let file = file.as_mut().ok_or(Error::NotFound)?;
+            self.find(file)?;
+            f(file)
+        })
+    }
+
+    // Returns an error if the key does not exist in the file.
+    fn find(&self, file: &RegistryFile) -> Result<()> {
+        match file.exists(&self.path) {
+            true => Ok(()),
+            false => Err(Error::NotFound),
+        }
+    }
+
+    fn child(&self, name: &str) -> WineKey {
+        WineKey {
+            file: self.file.clone(),
+            path: format!(r"{}\{}", self.path, name),
+            name: name.to_string(),
+        }
+    }
+}
+
+impl StoreKey for WineKey {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn open_subkey(&self, name: &str) -> Result<Self> {
+        self.read(|file| {
+            file.children(&self.path)
+                .into_iter()
+                .find(|n| n.to_uppercase() == name.to_uppercase())
+                .map(|n| self.child(&n))
+                .ok_or(Error::NotFound)
+        })
+    }
+
+    fn create_subkey(&self, name: &str) -> Result<Self> {
+        self.write(|file| {
+            let upper = name.to_uppercase();
+            if let Some(n) = file
+                .children(&self.path)
+                .into_iter()
+                .find(|n| n.to_uppercase() == upper)
+            {
+                return Ok((self.child(&n), false));
+            }
+
+            let key = self.child(name);
+            file.insert(&key.path);
+            Ok((key, true))
+        })
+    }
+
+    fn delete_subkey(&self, name: &str) -> Result<()> {
+        self.write(|file| {
+            let child = self.child(name);
+            let path = components(&child.path);
+            let count = file.sections.len();
+            file.sections
+                .retain(|s| !starts_with(&components(&s.path), &path));
+            if file.sections.len() == count {
+                return Err(Error::NotFound);
+            }
+
+            // Keep this key if it was only implied by the deleted subkey.
+            if !file.exists(&self.path) {
+                file.insert(&self.path);
+            }
+            Ok(((), true))
+        })
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        self.read(|file| Ok(file.children(&self.path)))
+    }
+
+    fn values(&self) -> Result<Vec<(Option<String>, Data)>> {
+        self.read(|file| {
+            let Some(section) = file.section(&self.path) else {
+                return Ok(Vec::new());
+            };
+
+            // Skip values with types that cannot be represented.
+            Ok(section
+                .values()
+                .filter_map(|(_, name, data)| Some((name, parse_data(data)?)))
+                .collect())
+        })
+    }
+
+    fn value(&self, name: Option<&str>) -> Result<Data> {
+        self.read(|file| {
+            let (_, data) = file
+                .section(&self.path)
+                .and_then(|s| s.value(name))
+                .ok_or(Error::NotFound)?;
+            parse_data(data).ok_or(Error::Format)
+        })
+    }
+
+    fn set_value(&self, name: Option<&str>, data: Data) -> Result<()> {
+        self.write(|file| {
+            if file.section(&self.path).is_none() {
+                file.insert(&self.path);
+            }
+
+            let section = file.section_mut(&self.path).ok_or(Error::NotFound)?;
+            let line = format_value(name, &data);
+            match section.value(name) {
+                Some((index, _)) => section.lines[index] = line,
+                None => section.lines.push(line),
+            }
+            Ok(((), true))
+        })
+    }
+
+    fn delete_value(&self, name: Option<&str>) -> Result<()> {
+        self.write(|file| {
+            let section = file.section_mut(&self.path).ok_or(Error::NotFound)?;
+            let (index, _) = section.value(name).ok_or(Error::NotFound)?;
+            section.lines.remove(index);
+            Ok(((), true))
+        })
+    }
+}
+
+// A registry file with the content outside each key kept as written.
+#[derive(Debug)]
+struct RegistryFile {
+    preamble: Vec<String>,
+    sections: Vec<Section>,
+}
+
+// A key and the lines that follow it, which may span multiple physical lines when continued.
+#[derive(Debug)]
+struct Section {
+    path: String,
+    suffix: String,
+    lines: Vec<String>,
+}
+
+impl RegistryFile {
+    fn new(scope: Scope) -> Self {
+        let relative = match scope {
+            Scope::User => r";; All keys relative to \\User\\S-1-5-21-0-0-0-1000",
+            Scope::Machine => r";; All keys relative to \\Machine",
+        };
+
+        RegistryFile {
+            preamble: vec![HEADER.to_string(), relative.to_string()],
+            sections: Vec::new(),
+        }
+    }
+
+    // Loads a file, or `None` if it does not exist.
+    fn load(path: &Path) -> Result<Option<Self>> {
+        let s = match fs::read_to_string(path) {
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            s => s?,
+        };
+        RegistryFile::parse(&s).map(Some)
+    }
+
+    fn parse(s: &str) -> Result<Self> {
+        let mut file = RegistryFile {
+            preamble: Vec::new(),
+            sections: Vec::new(),
+        };
+
+        let mut lines = s.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let mut line = line.to_string();
+            while line.trim_end().ends_with(",\\") {
+                match lines.next() {
+                    Some((_, next)) => {
+                        line.push('\n');
+                        line.push_str(next);
+                    }
+                    None => break,
+                }
+            }
+
+            if index == 0 {
+                if line != HEADER {
+                    return Err(regfile::error(1, 1, "expected Wine registry header"));
+                }
+            } else if let Some(rest) = line.strip_prefix('[') {
+                let (path, suffix) = unescape(rest, ']')
+                    .ok_or_else(|| regfile::error(index + 1, 1, "expected ']'"))?;
+                file.sections.push(Section {
+                    path,
+                    suffix: suffix.to_string(),
+                    lines: Vec::new(),
+                });
+                continue;
+            }
+
+            match file.sections.last_mut() {
+                Some(_) if line.trim().is_empty() => {}
+                Some(section) => section.lines.push(line),
+                None => file.preamble.push(line),
+            }
+        }
+
+        if file.preamble.is_empty() {
+            return Err(regfile::error(1, 1, "expected Wine registry header"));
+        }
+        while file.preamble.last().is_some_and(|l| l.trim().is_empty()) {
+            file.preamble.pop();
+        }
+
+        Ok(file)
+    }
+
+    // Writes to a temporary file and renames it over the file so that readers never see partial content.
+    fn save(&self, path: &Path) -> Result<()> {
+        let mut out = String::new();
+        for line in &self.preamble {
+            out.push_str(line);
+            out.push('\n');
+        }
+        for section in &self.sections {
+            out.push('\n');
+            out.push('[');
+            out.push_str(&escape(&section.path));
+            out.push(']');
+            out.push_str(&section.suffix);
+            out.push('\n');
+            for line in &section.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(format!(
+            ".{}-{}.tmp",
+            std::process::id(),
+            TEMP_COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temp, out)?;
+        if let Err(err) = fs::rename(&temp, path) {
+            let _ = fs::remove_file(&temp);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let path = components(path);
+        self.sections
+            .iter()
+            .any(|s| starts_with(&components(&s.path), &path))
+    }
+
+    // Gets the names of subkeys in the order first written.
+    fn children(&self, path: &str) -> Vec<String> {
+        let path = components(path);
+        let mut children: Vec<String> = Vec::new();
+        for section in &self.sections {
+            let components = components(&section.path);
+            if components.len() > path.len() && starts_with(&components, &path) {
+                let name = components[path.len()];
+                if !children
+                    .iter()
+                    .any(|c| c.to_uppercase() == name.to_uppercase())
+                {
+                    children.push(name.to_string());
+                }
+            }
+        }
+        children
+    }
+
+    fn section(&self, path: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.is(path))
+    }
+
+    fn section_mut(&mut self, path: &str) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|s| s.is(path))
+    }
+
+    // Adds a key in sorted order like Wine writes them.
+    fn insert(&mut self, path: &str) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let filetime =
+            (now.as_secs() + EPOCH_DIFFERENCE) * 10_000_000 + now.subsec_nanos() as u64 / 100;
+
+        let upper = path.to_uppercase();
+        let index = self
+            .sections
+            .iter()
+            .position(|s| s.path.to_uppercase() > upper)
+            .unwrap_or(self.sections.len());
+        self.sections.insert(
+            index,
+            Section {
+                path: path.to_string(),
+                suffix: format!(" {}", now.as_secs()),
+                lines: vec![format!("#time={:x}", filetime)],
+            },
+        );
+    }
+}
+
+impl Section {
+    fn is(&self, path: &str) -> bool {
+        let path = components(path);
+        let components = components(&self.path);
+        components.len() == path.len() && starts_with(&components, &path)
+    }
+
+    // Gets the index, name, and unparsed data of each value.
+    fn values(&self) -> impl Iterator<Item = (usize, Option<String>, &str)> {
+        self.lines.iter().enumerate().filter_map(|(index, line)| {
+            let (name, data) = if let Some(rest) = line.strip_prefix('@') {
+                (None, rest)
+            } else {
+                let (name, rest) = unescape(line.strip_prefix('"')?, '"')?;
+                (Some(name), rest)
+            };
+            Some((index, name, data.trim_start().strip_prefix('=')?))
+        })
+    }
+
+    fn value(&self, name: Option<&str>) -> Option<(usize, &str)> {
+        let name = name.map(str::to_uppercase);
+        self.values()
+            .find(|(_, n, _)| n.as_ref().map(|n| n.to_uppercase()) == name)
+            .map(|(index, _, data)| (index, data))
+    }
+}
+
+fn components(path: &str) -> Vec<&str> {
+    path.split('\\').filter(|s| !s.is_empty()).collect()
+}
+
+fn starts_with(path: &[&str], prefix: &[&str]) -> bool {
+    path.len() >= prefix.len()
+        && path
+            .iter()
+            .zip(prefix)
+            .all(|(a, b)| a.to_uppercase() == b.to_uppercase())
+}
+
+fn parse_data(s: &str) -> Option<Data> {
+    let s = s.replace("\\\n", "");
+    let s = s.trim();
+
+    if let Some(rest) = s.strip_prefix('"') {
+        return match unescape(rest, '"')? {
+            (value, "") => Some(Data::String(value)),
+            _ => None,
+        };
+    }
+
+    if let Some(rest) = s.strip_prefix("str(") {
+        let (kind, rest) = rest.split_once("):\"")?;
+        let (value, "") = unescape(rest, '"')? else {
+            return None;
+        };
+        return match kind {
+            // REG_SZ and REG_EXPAND_SZ
+            "1" | "2" => Some(Data::String(value)),
+            "7" => Some(Data::MultiString(
+                value
+                    .split('\0')
+                    .filter(|s| !s.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            )),
+            _ => None,
+        };
+    }
+
+    regfile::parse_data(s).ok().flatten()
+}
+
+fn format_value(name: Option<&str>, data: &Data) -> String {
+    let name = match name {
+        Some(name) => format!("\"{}\"", escape(name)),
+        None => "@".to_string(),
+    };
+
+    match data {
+        Data::String(s) => format!("{}=\"{}\"", name, escape(s)),
+        Data::MultiString(v) => {
+            let value: String = v.iter().map(|s| format!("{}\0", s)).collect();
+            format!("{}=str(7):\"{}\"", name, escape(&value))
+        }
+        data => {
+            let mut out = String::new();
+            regfile::write_value(&mut out, &name, data);
+            out.truncate(out.trim_end().len());
+            out
+        }
+    }
+}
+
+// Escapes a string like Wine, using \x escapes for characters that are not printable ASCII.
+fn escape(s: &str) -> String {
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push_str(r"\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str(r"\n"),
+            '\r' => out.push_str(r"\r"),
+            '\t' => out.push_str(r"\t"),
+            // An octal digit following \0 would be read as part of the escape.
+            '\0' if !chars.peek().is_some_and(|c| c.is_digit(8)) => out.push_str(r"\0"),
+            ' '..='~' => out.push(c),
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.push_str(&format!(r"\x{:04x}", unit));
+                }
+            }
+        }
+    }
+    out
+}
+
+// Unescapes a string up to an unescaped `end` character, returning the string and any content after it.
+fn unescape(s: &str, end: char) -> Option<(String, &str)> {
+    let mut units: Vec<u16> = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == end {
+            return Some((String::from_utf16_lossy(&units), &s[i + c.len_utf8()..]));
+        }
+        if c != '\\' {
+            units.extend(c.encode_utf16(&mut [0; 2]).iter());
+            continue;
+        }
+
+        let (_, c) = chars.next()?;
+        let unit = match c {
+            'a' => 0x07,
+            'b' => 0x08,
+            'e' => 0x1b,
+            'f' => 0x0c,
+            'n' => 0x0a,
+            'r' => 0x0d,
+            't' => 0x09,
+            'v' => 0x0b,
+            'x' => digits(&mut chars, 16, 4).unwrap_or('x' as u16),
+            '0'..='7' => {
+                let mut unit = c as u16 - '0' as u16;
+                for _ in 0..2 {
+                    let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(8)) else {
+                        break;
+                    };
+                    chars.next();
+                    unit = unit * 8 + digit as u16;
+                }
+                unit
+            }
+            c => {
+                units.extend(c.encode_utf16(&mut [0; 2]).iter());
+                continue;
+            }
+        };
+        units.push(unit);
+    }
+    None
+}
+
+// Reads up to `max` digits in a radix, or `None` if there are none.
+fn digits(
+    chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>,
+    radix: u32,
+    max: usize,
+) -> Option<u16> {
+    let mut value: Option<u16> = None;
+    for _ in 0..max {
+        let Some(digit) = chars.peek().and_then(|(_, c)| c.to_digit(radix)) else {
+            break;
+        };
+        chars.next();
+        value = Some(value.unwrap_or(0).wrapping_mul(radix as u16) + digit as u16);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        check_dependents, get_provider, register_dependent, unregister_dependent, Provider, Version,
+    };
+
+    const SYSTEM_REG: &str = r#"WINE REGISTRY Version 2
+;; All keys relative to \\Machine
+
+#arch=win64
+
+[Software\\Classes\\Installer\\Dependencies\\runtime] 1700000000
+#time=1da1a2b3c4d5e6f
+@="{F8E9F4B1-0000-0000-0000-000000000000}"
+"DisplayName"="Runtime \"x64\" \x00e9t\x00e9"
+"Version"="1.2.3.4"
+"Attributes"=dword:00000100
+
+[Software\\Classes\\Installer\\Dependencies\\runtime\\Dependents\\bundle] 1700000000
+"MinVersion"="1.0"
+"Data"=hex:00,01,02,03,04,05,06,07,08,09,0a,0b,0c,0d,0e,0f,10,11,12,13,14,15,16,17,\
+  18,19
+"Multi"=str(7):"a\0b\0"
+
+[Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{F8E9F4B1-0000-0000-0000-000000000000}] 1700000000
+"DisplayName"="Example Runtime"
+
+[Software\\Wine] 1700000000
+"Version"="win10"
+
+[Software\\Wow6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{E0000000-0000-0000-0000-000000000000}] 1700000000
+"DisplayName"="Example Tools (x86)"
+"#;
+
+    fn prefix(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("wixpkgdep-wine-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn read() {
+        let path = prefix("read");
+        fs::write(path.join("system.reg"), SYSTEM_REG).unwrap();
+        let store = WineRegistry::new(&path);
+
+        let provider = get_provider(&store, "RUNTIME", Scope::Machine).unwrap();
+        assert_eq!(provider.key, "RUNTIME");
+        assert_eq!(provider.name, "Runtime \"x64\" été");
+        assert_eq!(provider.version, Version::from([1, 2, 3, 4]));
+        assert_eq!(
+            provider.id.as_deref(),
+            Some("{F8E9F4B1-0000-0000-0000-000000000000}")
+        );
+        assert_eq!(
+            provider.attributes,
+            Some(crate::Attributes::MIN_VERSION_INCLUSIVE)
+        );
+
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents[0].key, "bundle");
+        assert_eq!(dependents[0].min_version, Some(Version::from([1, 0, 0, 0])));
+
+        let key = store
+            .open(Scope::Machine)
+            .and_then(|k| k.open_subkey("runtime"))
+            .and_then(|k| k.open_subkey("Dependents"))
+            .and_then(|k| k.open_subkey("bundle"))
+            .unwrap();
+        assert_eq!(
+            key.value(Some("Data")).unwrap(),
+            Data::Binary((0..26).collect())
+        );
+        assert_eq!(
+            key.value(Some("Multi")).unwrap(),
+            Data::MultiString(vec!["a".to_string(), "b".to_string()])
+        );
+
+        let uninstall = store.open_uninstall(Scope::Machine).unwrap();
+        assert_eq!(uninstall[0].name(), "Uninstall");
+        assert_eq!(
+            uninstall[0].keys().unwrap(),
+            vec!["{F8E9F4B1-0000-0000-0000-000000000000}"]
+        );
+        assert_eq!(
+            uninstall[1].keys().unwrap(),
+            vec!["{E0000000-0000-0000-0000-000000000000}"]
+        );
+
+        assert_eq!(store.open(Scope::User).unwrap_err(), Error::NotFound);
+        assert!(store.open_uninstall(Scope::User).unwrap().is_empty());
+
+        // Reading does not create lock files, so prefixes that cannot be written can be read.
+        assert!(!path.join("system.reg.lock").exists());
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn write() {
+        let path = prefix("write");
+        fs::write(path.join("system.reg"), SYSTEM_REG).unwrap();
+        let store = WineRegistry::new(&path);
+
+        Provider {
+            key: "tools".to_string(),
+            name: "Tools".to_string(),
+            version: Version::from([2, 0, 0, 0]),
+            ..Default::default()
+        }
+        .register(&store, Scope::Machine)
+        .unwrap();
+        register_dependent(&store, "tools", "app", Scope::Machine, None, None, None).unwrap();
+        unregister_dependent(&store, "runtime", "bundle", Scope::Machine).unwrap();
+
+        let store = WineRegistry::new(&path);
+        let provider = get_provider(&store, "tools", Scope::Machine).unwrap();
+        assert_eq!(provider.name, "Tools");
+        assert_eq!(
+            check_dependents(&store, "tools", Scope::Machine, None, None)
+                .unwrap()
+                .unwrap()[0]
+                .key,
+            "app"
+        );
+        assert!(
+            check_dependents(&store, "runtime", Scope::Machine, None, None)
+                .unwrap()
+                .is_none()
+        );
+
+        // Content outside the changed keys is preserved and new keys are sorted.
+        let text = fs::read_to_string(path.join("system.reg")).unwrap();
+        assert!(text.starts_with(
+            "WINE REGISTRY Version 2\n;; All keys relative to \\\\Machine\n\n#arch=win64\n\n[Software\\\\Classes\\\\Installer\\\\Dependencies\\\\runtime] 1700000000\n#time=1da1a2b3c4d5e6f\n"
+        ));
+        assert!(text.contains("\n[Software\\\\Wine] 1700000000\n\"Version\"=\"win10\"\n"));
+        assert!(text.ends_with("\n\"DisplayName\"=\"Example Tools (x86)\"\n"));
+        assert!(text.contains("\n\"DisplayName\"=\"Tools\"\n\"Version\"=\"2.0.0.0\"\n"));
+        assert!(
+            text.find("Dependencies\\\\tools\\\\Dependents\\\\app]")
+                .unwrap()
+                < text.find("[Software\\\\Wine]").unwrap()
+        );
+
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn create() {
+        let path = prefix("create");
+        let store = WineRegistry::new(&path);
+        assert_eq!(store.open(Scope::User).unwrap_err(), Error::NotFound);
+
+        let key = store.create(Scope::User).unwrap();
+        key.set_value(Some("Count"), Data::QWord(1)).unwrap();
+        assert_eq!(key.value(Some("count")).unwrap(), Data::QWord(1));
+
+        let text = fs::read_to_string(path.join("user.reg")).unwrap();
+        assert!(text.starts_with("WINE REGISTRY Version 2\n;; All keys relative to \\\\User\\\\"));
+        assert!(text.contains("\"Count\"=hex(b):01,00,00,00,00,00,00,00\n"));
+
+        store
+            .create(Scope::User)
+            .unwrap()
+            .delete_value(Some("Count"))
+            .unwrap();
+        assert!(key.values().unwrap().is_empty());
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn exclusive() {
+        let path = prefix("exclusive");
+        fs::write(path.join("system.reg"), SYSTEM_REG).unwrap();
+        let store = WineRegistry::new(&path);
+
+        store
+            .exclusive(Scope::Machine, || {
+                register_dependent(&store, "runtime", "app", Scope::Machine, None, None, None)?;
+                unregister_dependent(&store, "runtime", "bundle", Scope::Machine)?;
+
+                // Changes are visible within the operation but only written once it completes.
+                let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)?;
+                assert_eq!(dependents.unwrap()[0].key, "app");
+                assert_eq!(
+                    fs::read_to_string(path.join("system.reg")).unwrap(),
+                    SYSTEM_REG
+                );
+                Ok(())
+            })
+            .unwrap();
+
+        let dependents = check_dependents(&store, "runtime", Scope::Machine, None, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].key, "app");
+        assert!(PENDING.with_borrow(Vec::is_empty));
+
+        // Only the registry and lock files remain.
+        let mut files: Vec<_> = fs::read_dir(&path)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["system.reg", "system.reg.lock"]);
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_file() {
+        let path = prefix("invalid");
+        fs::write(path.join("system.reg"), "REGEDIT4\n").unwrap();
+        assert!(matches!(
+            WineRegistry::new(&path).open(Scope::Machine),
+            Err(Error::Parse { line: 1, .. })
+        ));
+        fs::remove_dir_all(path).unwrap();
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(escape("a\0b\0"), r"a\0b\0");
+        assert_eq!(escape("\x001"), r"\x00001");
+        assert_eq!(escape("\\\"\n😀"), r#"\\\"\n\xd83d\xde00"#);
+
+        for s in ["a\0b\0", "\x001", "\\\"\n😀", "tab\tend"] {
+            assert_eq!(
+                unescape(&format!("{}\"rest", escape(s)), '"'),
+                Some((s.to_string(), "rest"))
+            );
+        }
+        assert_eq!(unescape(r"\101\x41]", ']'), Some(("AA".to_string(), "")));
+        assert_eq!(unescape("unterminated", '"'), None);
+    }
+}
@@ -0,0 +1,392 @@
+// Copyright 2023 Heath Stewart.
+// Licensed under the MIT License. See LICENSE.txt in the project root for license information.
+
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+
+use crate::{Attributes, Dependency, Error, Provider, Result, Version};
+
+// cspell:ignore roxmltree
+
+/// The key of a provider authored in a package with an automatically generated product code, which WiX replaces
+/// with the product code when the package is built.
+pub const PRODUCT_CODE_KEY: &str = "!(bind.property.ProductCode)";
+
+/// A provider authored with a `Provides` element.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthoredProvider {
+    /// The provider, with its key, version, and display name defaulted from the parent package where not authored.
+    ///
+    /// The key is [`PRODUCT_CODE_KEY`] if neither it nor the product code of the package is authored.
+    pub provider: Provider,
+
+    /// Dependencies required by the provider from nested `Requires` and `RequiresRef` elements.
+    pub requires: Vec<Dependency>,
+
+    /// Line on which the `Provides` element starts.
+    pub line: u32,
+}
+
+/// A dependency authored with a `Requires` element.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthoredDependency {
+    /// Optional identifier referenced by `RequiresRef` elements.
+    pub id: Option<String>,
+
+    /// The required provider key and version range.
+    pub dependency: Dependency,
+
+    /// Line on which the `Requires` element starts.
+    pub line: u32,
+}
+
+/// Dependency authoring from the `Provides`, `Requires`, and `RequiresRef` elements of WiX v3 or v4 source.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WixAuthoring {
+    /// Providers in document order.
+    pub providers: Vec<AuthoredProvider>,
+
+    /// All `Requires` elements in document order, whether nested within a `Provides` element or not.
+    pub dependencies: Vec<AuthoredDependency>,
+}
+
+impl WixAuthoring {
+    /// Parses the dependency authoring from WiX source.
+    ///
+    /// Elements are matched by name in any namespace, since the Dependency extension namespace differs between WiX
+    /// v3 and v4. Preprocessor variables must already be resolved, while binder variables like
+    /// `!(bind.FileVersion.App.exe)` are treated as if not authored. Returns [`Error::Parse`] with the line and column
+    /// of invalid XML or authoring.
+    pub fn from_wxs(s: &str) -> Result<Self> {
+        let doc = parse(s)?;
+
+        let mut authoring = WixAuthoring::default();
+        let elements = doc.descendants().filter(Node::is_element);
+        for node in elements
+            .clone()
+            .filter(|n| n.tag_name().name() == "Requires")
+        {
+            authoring.dependencies.push(AuthoredDependency {
+                id: node.attribute("Id").map(str::to_string),
+                dependency: requires(&doc, node)?,
+                line: line(&doc, node),
+            });
+        }
+
+        for node in elements.filter(|n| n.tag_name().name() == "Provides") {
+            let mut requires = Vec::new();
+            for child in node.children().filter(Node::is_element) {
+                match child.tag_name().name() {
+                    "Requires" => requires.push(self::requires(&doc, child)?),
+                    "RequiresRef" => {
+                        let id = child.attribute("Id").unwrap_or_default();
+                        let dependency = authoring
+                            .dependencies
+                            .iter()
+                            .find(|d| d.id.as_deref() == Some(id))
+                            .ok_or_else(|| {
+                                error(&doc, child, format!("undefined Requires {:?}", id))
+                            })?;
+                        requires.push(dependency.dependency.clone());
+                    }
+                    _ => {}
+                }
+            }
+
+            authoring.providers.push(AuthoredProvider {
+                provider: provides(&doc, node)?,
+                requires,
+                line: line(&doc, node),
+            });
+        }
+
+        Ok(authoring)
+    }
+
+    /// Reads the dependency authoring from a WiX source file.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        WixAuthoring::from_wxs(&std::fs::read_to_string(path)?)
+    }
+}
+
+fn provides(doc: &Document, node: Node) -> Result<Provider> {
+    // Providers are typically authored within a component, so defaults are taken from the enclosing package.
+    let package = node
+        .ancestors()
+        .find(|n| matches!(n.tag_name().name(), "Product" | "Package"));
+    let package_attribute = |name| {
+        package
+            .and_then(|p| p.attribute(name))
+            .filter(|v| *v != "*" && resolved(v))
+    };
+
+    // Providers of a package default to its product code, version, and name.
+    let id = package_attribute("ProductCode").or_else(|| match package {
+        Some(p) if p.tag_name().name() == "Product" => package_attribute("Id"),
+        _ => None,
+    });
+    let key = node
+        .attribute("Key")
+        .or(id)
+        .or(package.map(|_| PRODUCT_CODE_KEY))
+        .ok_or_else(|| error(doc, node, "missing Key attribute"))?;
+    let version = match node
+        .attribute("Version")
+        .filter(|v| resolved(v))
+        .or_else(|| package_attribute("Version"))
+    {
+        Some(version) => self::version(doc, node, version)?,
+        None => Version::default(),
+    };
+
+    Ok(Provider {
+        key: key.to_string(),
+        name: node
+            .attribute("DisplayName")
+            .or_else(|| package_attribute("Name"))
+            .unwrap_or_default()
+            .to_string(),
+        version,
+        id: id.map(str::to_string),
+        attributes: None,
+    })
+}
+
+fn requires(doc: &Document, node: Node) -> Result<Dependency> {
+    let key = node
+        .attribute("ProviderKey")
+        .ok_or_else(|| error(doc, node, "missing ProviderKey attribute"))?;
+
+    let version = |name| {
+        node.attribute(name)
+            .filter(|v| resolved(v))
+            .map(|v| self::version(doc, node, v))
+            .transpose()
+    };
+    let min_version = version("Minimum")?;
+    let max_version = version("Maximum")?;
+
+    let mut attributes = Attributes::NONE;
+    if yes(doc, node, "IncludeMinimum")? {
+        attributes |= Attributes::MIN_VERSION_INCLUSIVE;
+    }
+    if yes(doc, node, "IncludeMaximum")? {
+        attributes |= Attributes::MAX_VERSION_INCLUSIVE;
+    }
+
+    Ok(Dependency {
+        key: key.to_string(),
+        min_version,
+        max_version,
+        attributes: (!attributes.is_empty()).then_some(attributes),
+    })
+}
+
+// Whether a value is not a binder variable e.g., `!(bind.FileVersion.App.exe)`, which is only resolved when the
+// package is built.
+fn resolved(value: &str) -> bool {
+    !value.starts_with("!(")
+}
+
+pub(crate) fn version(doc: &Document, node: Node, value: &str) -> Result<Version> {
+    Version::try_from(value).map_err(|_| error(doc, node, format!("invalid version {:?}", value)))
+}
+
+pub(crate) fn yes(doc: &Document, node: Node, name: &str) -> Result<bool> {
+    match node.attribute(name).map(str::to_lowercase).as_deref() {
+        None | Some("no") | Some("false") => Ok(false),
+        Some("yes") | Some("true") => Ok(true),
+        Some(value) => Err(error(
+            doc,
+            node,
+            format!("invalid {} value {:?}", name, value),
+        )),
+    }
+}
+
+fn line(doc: &Document, node: Node) -> u32 {
+    doc.text_pos_at(node.range().start).row
+}
+
+pub(crate) fn parse(s: &str) -> Result<Document<'_>> {
+    Document::parse(s).map_err(|err| {
+        let pos = err.pos();
+        Error::Parse {
+            line: pos.row as usize,
+            column: pos.col as usize,
+            message: err.to_string(),
+        }
+    })
+}
+
+pub(crate) fn error(doc: &Document, node: Node, message: impl Into<String>) -> Error {
+    let pos = doc.text_pos_at(node.range().start);
+    Error::Parse {
+        line: pos.row as usize,
+        column: pos.col as usize,
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_wxs_v3() {
+        let authoring = WixAuthoring::from_wxs(
+            r#"<?xml version="1.0"?>
+<Wix xmlns="http://schemas.microsoft.com/wix/2006/wi"
+     xmlns:dep="http://schemas.microsoft.com/wix/DependencyExtension">
+  <Product Id="{F8E9F4B1-0000-0000-0000-000000000000}" Name="App" Version="1.2.3.4" Manufacturer="Example">
+    <dep:Provides Key="Example.App">
+      <dep:Requires Id="Runtime" ProviderKey="Example.Runtime" Minimum="2.0" Maximum="3.0" IncludeMinimum="yes" />
+    </dep:Provides>
+  </Product>
+</Wix>"#,
+        )
+        .unwrap();
+
+        let provider = &authoring.providers[0];
+        assert_eq!(provider.provider.key, "Example.App");
+        assert_eq!(provider.provider.name, "App");
+        assert_eq!(provider.provider.version, Version::from([1, 2, 3, 4]));
+        assert_eq!(
+            provider.provider.id.as_deref(),
+            Some("{F8E9F4B1-0000-0000-0000-000000000000}")
+        );
+        assert_eq!(provider.line, 5);
+
+        let dependency = &provider.requires[0];
+        assert_eq!(dependency.key, "Example.Runtime");
+        assert_eq!(dependency.range().to_string(), "[2.0.0.0,3.0.0.0)");
+
+        assert_eq!(authoring.dependencies.len(), 1);
+        assert_eq!(authoring.dependencies[0].id.as_deref(), Some("Runtime"));
+        assert_eq!(authoring.dependencies[0].line, 6);
+    }
+
+    #[test]
+    fn from_wxs_v4() {
+        let authoring = WixAuthoring::from_wxs(
+            r#"<Wix xmlns="http://wixtoolset.org/schemas/v4/wxs"
+     xmlns:dep="http://wixtoolset.org/schemas/v4/wxs/dependency">
+  <Package ProductCode="*" Name="Tools" Version="2.0" Manufacturer="Example">
+    <Provides Key="Example.Tools" DisplayName="Example Tools">
+      <RequiresRef Id="Runtime" />
+    </Provides>
+  </Package>
+  <Fragment>
+    <Requires Id="Runtime" ProviderKey="Example.Runtime" Maximum="3.0" IncludeMaximum="true" />
+  </Fragment>
+</Wix>"#,
+        )
+        .unwrap();
+
+        let provider = &authoring.providers[0];
+        assert_eq!(provider.provider.key, "Example.Tools");
+        assert_eq!(provider.provider.name, "Example Tools");
+        assert_eq!(provider.provider.version, Version::from([2, 0, 0, 0]));
+        assert_eq!(provider.provider.id, None);
+        assert_eq!(provider.requires[0].range().to_string(), "(,3.0.0.0]");
+    }
+
+    #[test]
+    fn from_wxs_default_key() {
+        let authoring = WixAuthoring::from_wxs(
+            r#"<Wix><Product Id="{F8E9F4B1-0000-0000-0000-000000000000}" Version="1.0"><Provides /></Product></Wix>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            authoring.providers[0].provider.key,
+            "{F8E9F4B1-0000-0000-0000-000000000000}"
+        );
+
+        for s in [
+            r#"<Wix><Product Id="*"><dep:Provides xmlns:dep="http://schemas.microsoft.com/wix/DependencyExtension" /></Product></Wix>"#,
+            r#"<Wix><Package Name="App"><Provides /></Package></Wix>"#,
+        ] {
+            let provider = &WixAuthoring::from_wxs(s).unwrap().providers[0].provider;
+            assert_eq!(provider.key, PRODUCT_CODE_KEY);
+            assert_eq!(provider.id, None);
+        }
+    }
+
+    #[test]
+    fn from_wxs_component() {
+        let authoring = WixAuthoring::from_wxs(
+            r#"<Wix xmlns:dep="http://schemas.microsoft.com/wix/DependencyExtension">
+  <Product Id="{F8E9F4B1-0000-0000-0000-000000000000}" Name="App" Version="1.2">
+    <DirectoryRef Id="INSTALLFOLDER">
+      <Component Id="Provider">
+        <dep:Provides Key="Example.App" />
+      </Component>
+    </DirectoryRef>
+  </Product>
+</Wix>"#,
+        )
+        .unwrap();
+
+        let provider = &authoring.providers[0].provider;
+        assert_eq!(provider.key, "Example.App");
+        assert_eq!(provider.name, "App");
+        assert_eq!(provider.version, Version::from([1, 2, 0, 0]));
+        assert_eq!(
+            provider.id.as_deref(),
+            Some("{F8E9F4B1-0000-0000-0000-000000000000}")
+        );
+    }
+
+    #[test]
+    fn from_wxs_binder_variables() {
+        let authoring = WixAuthoring::from_wxs(
+            r#"<Wix>
+  <Package ProductCode="!(bind.property.ProductCode)" Name="App" Version="!(bind.FileVersion.App.exe)">
+    <Component><Provides /></Component>
+    <Requires ProviderKey="Example.Runtime" Minimum="!(bind.FileVersion.Runtime.dll)" />
+  </Package>
+</Wix>"#,
+        )
+        .unwrap();
+
+        let provider = &authoring.providers[0].provider;
+        assert_eq!(provider.key, PRODUCT_CODE_KEY);
+        assert_eq!(provider.version, Version::default());
+        assert_eq!(provider.id, None);
+        assert_eq!(authoring.dependencies[0].dependency.min_version, None);
+    }
+
+    #[test]
+    fn from_wxs_errors() {
+        let err = |s| WixAuthoring::from_wxs(s).unwrap_err();
+
+        assert_eq!(
+            err("<Wix>\n  <Requires ProviderKey=\"a\" Minimum=\"$(var.Version)\" />\n</Wix>"),
+            Error::Parse {
+                line: 2,
+                column: 3,
+                message: "invalid version \"$(var.Version)\"".to_string(),
+            }
+        );
+        assert!(matches!(
+            err("<Wix><Requires Minimum=\"1.0\" /></Wix>"),
+            Error::Parse { message, .. } if message == "missing ProviderKey attribute"
+        ));
+        assert!(matches!(
+            err("<Wix><Provides Key=\"a\"><RequiresRef Id=\"b\" /></Provides></Wix>"),
+            Error::Parse { message, .. } if message == "undefined Requires \"b\""
+        ));
+        assert!(matches!(
+            err("<Wix><Fragment><Provides /></Fragment></Wix>"),
+            Error::Parse { message, .. } if message == "missing Key attribute"
+        ));
+        assert!(matches!(
+            err("<Wix>\n<Provides Key=>\n</Wix>"),
+            Error::Parse { line: 2, .. }
+        ));
+    }
+}